@@ -0,0 +1,201 @@
+use std::time::Duration;
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AnimationMode {
+    #[default]
+    Loop,
+    /// Stops on the last frame
+    Once,
+}
+
+/// A sequence of frames in a texture atlas
+#[derive(Clone, Debug)]
+pub struct AnimationClip {
+    pub atlas: Handle<TextureAtlas>,
+    /// Atlas indices in playback order
+    pub frames: Vec<usize>,
+    /// Seconds each frame is shown, one entry per frame
+    pub durations: Vec<f32>,
+    pub mode: AnimationMode,
+}
+
+impl AnimationClip {
+    fn duration(&self, frame: usize) -> Duration {
+        Duration::from_secs_f32(self.durations.get(frame).copied().unwrap_or(0.1))
+    }
+}
+
+/// Named clips of an entity and the one currently playing
+#[derive(Component, Default, Debug)]
+pub struct Animator {
+    clips: HashMap<String, AnimationClip>,
+    current: Option<String>,
+    /// Position in the frames of the current clip
+    frame: usize,
+    timer: Timer,
+    /// Set when a new clip started and the atlas still has to be swapped
+    changed: bool,
+    finished: bool,
+}
+
+impl Animator {
+    pub fn new<S: Into<String>>(clips: impl IntoIterator<Item = (S, AnimationClip)>) -> Self {
+        Self {
+            clips: clips
+                .into_iter()
+                .map(|(name, clip)| (name.into(), clip))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Switches to the clip from its first frame
+    ///
+    /// Does nothing if the clip is already playing or doesn't exist,
+    /// clips loaded from assets may not be there yet.
+    pub fn play(&mut self, name: &str) {
+        if self.current.as_deref() == Some(name) || !self.clips.contains_key(name) {
+            return;
+        }
+        self.current = Some(name.to_owned());
+        self.changed = true;
+    }
+
+    /// Replaces every clip, the current one restarts so it picks up the new frames
+    pub fn set_clips(&mut self, clips: HashMap<String, AnimationClip>) {
+        self.clips = clips;
+        self.current = None;
+    }
+}
+
+/// Labels of the states an entity is in, like `"grounded"` or `"hurt"`
+///
+/// Each feature keeps the labels it knows about up to date,
+/// the animator only matches them against the rules.
+#[derive(Component, Clone, Default, Debug)]
+pub struct AnimationState(HashSet<&'static str>);
+
+impl AnimationState {
+    pub fn set(&mut self, label: &'static str, active: bool) {
+        if active {
+            self.0.insert(label);
+        } else {
+            self.0.remove(label);
+        }
+    }
+
+    pub fn is(&self, label: &str) -> bool {
+        self.0.contains(label)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum AnimationCondition {
+    Always,
+    /// The entity is in the state with this label
+    State(&'static str),
+    Not(Box<AnimationCondition>),
+    All(Vec<AnimationCondition>),
+}
+
+impl AnimationCondition {
+    pub fn holds(&self, state: &AnimationState) -> bool {
+        match self {
+            AnimationCondition::Always => true,
+            AnimationCondition::State(label) => state.is(label),
+            AnimationCondition::Not(condition) => !condition.holds(state),
+            AnimationCondition::All(conditions) => {
+                conditions.iter().all(|condition| condition.holds(state))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AnimationRule {
+    pub condition: AnimationCondition,
+    pub clip: String,
+}
+
+impl AnimationRule {
+    pub fn new(condition: AnimationCondition, clip: impl Into<String>) -> Self {
+        Self {
+            condition,
+            clip: clip.into(),
+        }
+    }
+}
+
+/// Transitions between clips, the first rule that holds picks the clip
+#[derive(Component, Clone, Default, Debug)]
+pub struct AnimationRules(pub Vec<AnimationRule>);
+
+#[derive(Bundle, Default)]
+pub struct AnimationBundle {
+    pub animator: Animator,
+    pub rules: AnimationRules,
+    pub state: AnimationState,
+}
+
+/// Runs after every system writing to `AnimationState`
+pub fn select_animation(mut query: Query<(&mut Animator, &AnimationRules, &AnimationState)>) {
+    for (mut animator, rules, state) in &mut query {
+        if let Some(rule) = rules.0.iter().find(|rule| rule.condition.holds(state)) {
+            animator.play(&rule.clip);
+        }
+    }
+}
+
+pub fn animate(
+    mut query: Query<(
+        &mut Animator,
+        &mut TextureAtlasSprite,
+        &mut Handle<TextureAtlas>,
+    )>,
+    time: Res<Time>,
+) {
+    for (mut animator, mut sprite, mut atlas) in &mut query {
+        let Animator {
+            clips,
+            current,
+            frame,
+            timer,
+            changed,
+            finished,
+        } = &mut *animator;
+        let Some(clip) = current.as_ref().and_then(|name| clips.get(name)) else {
+            continue;
+        };
+        if clip.frames.is_empty() {
+            continue;
+        }
+        if *changed {
+            *atlas = clip.atlas.clone();
+            *frame = 0;
+            *timer = Timer::new(clip.duration(0), TimerMode::Once);
+            *changed = false;
+            *finished = false;
+            sprite.index = clip.frames[0];
+            continue;
+        }
+        if *finished || !timer.tick(time.delta()).finished() {
+            continue;
+        }
+
+        if *frame + 1 < clip.frames.len() {
+            *frame += 1;
+        } else if clip.mode == AnimationMode::Loop {
+            *frame = 0;
+        } else {
+            *finished = true;
+            continue;
+        }
+        sprite.index = clip.frames[*frame];
+        *timer = Timer::new(clip.duration(*frame), TimerMode::Once);
+    }
+}
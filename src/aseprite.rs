@@ -0,0 +1,314 @@
+use std::{fmt, path::Path};
+
+use bevy::{
+    asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    utils::{BoxedFuture, HashMap},
+};
+use serde::{
+    de::{MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
+
+use crate::animation::{AnimationClip, AnimationMode, Animator};
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+struct AsepriteRect {
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+}
+
+impl From<AsepriteRect> for Rect {
+    fn from(rect: AsepriteRect) -> Self {
+        Rect::new(rect.x, rect.y, rect.x + rect.w, rect.y + rect.h)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct AsepriteFrame {
+    frame: AsepriteRect,
+    /// Transparent borders were cut from the frame, which moves its content around
+    #[serde(default)]
+    trimmed: bool,
+    /// Milliseconds
+    duration: u32,
+}
+
+/// Frames are exported either as an array or as an object keyed by file name,
+/// the object form has to keep the order of its keys
+#[derive(Debug)]
+struct AsepriteFrames(Vec<AsepriteFrame>);
+
+impl<'de> Deserialize<'de> for AsepriteFrames {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FramesVisitor;
+
+        impl<'de> Visitor<'de> for FramesVisitor {
+            type Value = AsepriteFrames;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an array or a map of frames")
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> Result<Self::Value, A::Error> {
+                let mut frames = Vec::new();
+                while let Some(frame) = seq.next_element()? {
+                    frames.push(frame);
+                }
+                Ok(AsepriteFrames(frames))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut frames = Vec::new();
+                while let Some((_, frame)) = map.next_entry::<String, AsepriteFrame>()? {
+                    frames.push(frame);
+                }
+                Ok(AsepriteFrames(frames))
+            }
+        }
+
+        deserializer.deserialize_any(FramesVisitor)
+    }
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum AsepriteDirection {
+    #[default]
+    Forward,
+    Reverse,
+    Pingpong,
+    PingpongReverse,
+}
+
+#[derive(Deserialize, Debug)]
+struct AsepriteTag {
+    name: String,
+    from: usize,
+    to: usize,
+    #[serde(default)]
+    direction: AsepriteDirection,
+    /// Times to play the tag, missing or "0" loops forever
+    repeat: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AsepriteSliceKey {
+    pub frame: usize,
+    bounds: AsepriteRect,
+}
+
+impl AsepriteSliceKey {
+    /// Bounds in pixels of the frame, with the origin at its top left corner
+    pub fn bounds(&self) -> Rect {
+        self.bounds.into()
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct AsepriteSlice {
+    name: String,
+    keys: Vec<AsepriteSliceKey>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct AsepriteMeta {
+    image: String,
+    size: AsepriteSize,
+    #[serde(default)]
+    frame_tags: Vec<AsepriteTag>,
+    #[serde(default)]
+    slices: Vec<AsepriteSlice>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AsepriteSize {
+    w: f32,
+    h: f32,
+}
+
+/// Sprite sheet exported by Aseprite with "File > Export Sprite Sheet" as JSON data
+#[derive(Deserialize, Debug)]
+struct AsepriteExport {
+    frames: AsepriteFrames,
+    meta: AsepriteMeta,
+}
+
+impl AsepriteTag {
+    /// Counted repeats, `None` loops forever
+    fn times(&self) -> Option<usize> {
+        self.repeat
+            .as_deref()
+            .and_then(|repeat| repeat.parse().ok())
+            .filter(|times| *times > 0)
+    }
+
+    fn clip(&self, atlas: &Handle<TextureAtlas>, durations: &[f32]) -> AnimationClip {
+        let forward: Vec<usize> = (self.from..=self.to).collect();
+        let (frames, mode) = match self.times() {
+            None => (self.looped_frames(forward), AnimationMode::Loop),
+            Some(times) => (self.counted_frames(forward, times), AnimationMode::Once),
+        };
+        AnimationClip {
+            atlas: atlas.clone(),
+            durations: frames
+                .iter()
+                .map(|frame| durations.get(*frame).copied().unwrap_or(0.1))
+                .collect(),
+            frames,
+            mode,
+        }
+    }
+
+    /// One cycle of a looping tag
+    fn looped_frames(&self, forward: Vec<usize>) -> Vec<usize> {
+        match self.direction {
+            AsepriteDirection::Forward => forward,
+            AsepriteDirection::Reverse => forward.into_iter().rev().collect(),
+            // the end frames aren't repeated when bouncing back
+            AsepriteDirection::Pingpong => {
+                let back = forward.iter().rev().skip(1);
+                let back = back.take(forward.len().saturating_sub(2));
+                forward.iter().chain(back).copied().collect()
+            }
+            AsepriteDirection::PingpongReverse => {
+                let back = forward.iter().skip(1);
+                let back = back.take(forward.len().saturating_sub(2));
+                forward.iter().rev().chain(back).copied().collect()
+            }
+        }
+    }
+
+    /// Every frame of a tag played `times` times, it stops on the last one
+    ///
+    /// Like in Aseprite each direction of a ping-pong counts as one repeat,
+    /// and the frame it turns on isn't shown twice.
+    fn counted_frames(&self, forward: Vec<usize>, times: usize) -> Vec<usize> {
+        let backward: Vec<usize> = forward.iter().rev().copied().collect();
+        let (first, second) = match self.direction {
+            AsepriteDirection::Forward => return forward.repeat(times),
+            AsepriteDirection::Reverse => return backward.repeat(times),
+            AsepriteDirection::Pingpong => (forward, backward),
+            AsepriteDirection::PingpongReverse => (backward, forward),
+        };
+        let mut frames = first.clone();
+        for pass in 1..times {
+            let next = if pass % 2 == 1 { &second } else { &first };
+            frames.extend(next.iter().skip(1));
+        }
+        frames
+    }
+}
+
+#[derive(TypeUuid, TypePath, Debug)]
+#[uuid = "3c1f4a2e-8a0b-4f6d-9a54-5e1d6f0b7c21"]
+pub struct AsepriteSheet {
+    pub atlas: Handle<TextureAtlas>,
+    /// One clip per frame tag
+    pub clips: HashMap<String, AnimationClip>,
+    pub slices: HashMap<String, Vec<AsepriteSliceKey>>,
+}
+
+#[derive(Default)]
+pub struct AsepriteLoader;
+
+impl AssetLoader for AsepriteLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let export: AsepriteExport = serde_json::from_slice(bytes)?;
+            // trimmed frames would need an offset per frame to stay in place
+            if export.frames.0.iter().any(|frame| frame.trimmed) {
+                return Err(bevy::asset::Error::msg(format!(
+                    "{}: trimmed sprite sheets aren't supported, export without \"Trim Sprite\"",
+                    load_context.path().display()
+                )));
+            }
+
+            // the image path is relative to the json file
+            let image_path = load_context
+                .path()
+                .parent()
+                .unwrap_or(Path::new(""))
+                .join(&export.meta.image);
+            let image_path = AssetPath::new(image_path, None);
+            let texture: Handle<Image> = load_context.get_handle(image_path.clone());
+
+            let mut atlas =
+                TextureAtlas::new_empty(texture, Vec2::new(export.meta.size.w, export.meta.size.h));
+            let mut durations = Vec::with_capacity(export.frames.0.len());
+            for frame in &export.frames.0 {
+                atlas.add_texture(frame.frame.into());
+                durations.push(frame.duration as f32 / 1000.);
+            }
+            let atlas = load_context.set_labeled_asset("atlas", LoadedAsset::new(atlas));
+
+            let clips = export
+                .meta
+                .frame_tags
+                .iter()
+                .map(|tag| (tag.name.clone(), tag.clip(&atlas, &durations)))
+                .collect();
+            let slices = export
+                .meta
+                .slices
+                .into_iter()
+                .map(|slice| (slice.name, slice.keys))
+                .collect();
+
+            load_context.set_default_asset(
+                LoadedAsset::new(AsepriteSheet {
+                    atlas,
+                    clips,
+                    slices,
+                })
+                .with_dependency(image_path),
+            );
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["aseprite.json"]
+    }
+}
+
+/// Sheet the entity's `Animator` takes its clips from
+#[derive(Component, Default, Debug)]
+pub struct AsepriteAnimation(pub Handle<AsepriteSheet>);
+
+/// Fills animators once their sheet is loaded, and again whenever it's hot reloaded
+pub fn apply_aseprite_sheets(
+    mut events: EventReader<AssetEvent<AsepriteSheet>>,
+    sheets: Res<Assets<AsepriteSheet>>,
+    mut animators: Query<(&mut Animator, Ref<AsepriteAnimation>)>,
+) {
+    let mut changed = Vec::new();
+    for event in events.iter() {
+        match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                changed.push(handle.clone())
+            }
+            AssetEvent::Removed { .. } => (),
+        }
+    }
+
+    for (mut animator, sheet) in &mut animators {
+        if !sheet.is_added() && !changed.contains(&sheet.0) {
+            continue;
+        }
+        if let Some(sheet) = sheets.get(&sheet.0) {
+            animator.set_clips(sheet.clips.clone());
+        }
+    }
+}
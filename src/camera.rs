@@ -1,4 +1,4 @@
-use bevy::{prelude::*, render::camera::ScalingMode};
+use bevy::{prelude::*, render::camera::ScalingMode, window::PrimaryWindow};
 use bevy_ecs_ldtk::prelude::*;
 
 const ASPECT_RATIO: f32 = 16. / 9.;
@@ -8,10 +8,30 @@ use crate::player::Player;
 #[derive(Component)]
 pub struct GameCamera;
 
+/// Cursor position in world coordinates, `None` while it's outside the window
+#[derive(Resource, Default, Debug)]
+pub struct CursorWorldPosition(pub Option<Vec2>);
+
 pub fn setup_camera(mut commands: Commands) {
     commands.spawn((Camera2dBundle::default(), GameCamera));
 }
 
+pub fn update_cursor_world_position(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<GameCamera>>,
+    mut cursor_position: ResMut<CursorWorldPosition>,
+) {
+    let (Ok(window), Ok((camera, camera_transform))) =
+        (windows.get_single(), camera_query.get_single())
+    else {
+        return;
+    };
+    // goes through the orthographic projection, so it follows the level fitting below
+    cursor_position.0 = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor));
+}
+
 pub fn camera_fit_inside_current_level(
     mut camera_query: Query<
         (
@@ -6,10 +6,11 @@ use bevy_ecs_ldtk::prelude::*;
 use bevy_rapier2d::prelude::*;
 
 use crate::{
-    creature::{Damage, Health},
-    enemy::Enemy,
+    config::MovementConfig,
+    creature::{Damage, DamageEvent},
+    enemy::{Enemy, Spiked},
     ladder::{Climbable, Climber},
-    player::Player,
+    player::{Dash, PendingLaunch, Player, VerticalVelocity},
 };
 
 #[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
@@ -24,11 +25,23 @@ pub struct ColliderBundle {
     pub collision_groups: CollisionGroups,
 }
 
+/// Center offset and half extents of the local bounding box, before the transform scale
+pub fn collider_bounds(collider: &Collider) -> (Vec2, Vec2) {
+    let aabb = collider.raw.compute_local_aabb();
+    let center = aabb.center();
+    let half_extents = aabb.half_extents();
+    (
+        Vec2::new(center.x, center.y),
+        Vec2::new(half_extents.x, half_extents.y),
+    )
+}
+
 #[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
 pub struct SensorBundle {
     pub collider: Collider,
     pub sensor: Sensor,
     pub active_events: ActiveEvents,
+    pub active_collision_types: ActiveCollisionTypes,
     pub rotation_constraints: LockedAxes,
 }
 
@@ -38,6 +51,9 @@ pub enum GameCollisions {
     Mob,
     Wall,
     Item,
+    Platform,
+    Water,
+    Projectile,
     All,
     None,
 }
@@ -49,22 +65,50 @@ impl Into<CollisionGroups> for GameCollisions {
                 let wall_group: Group = Self::Wall.into();
                 let mob_group: Group = Self::Mob.into();
                 let item_group: Group = Self::Item.into();
-                CollisionGroups::new(Self::Player.into(), wall_group | mob_group | item_group)
+                let platform_group: Group = Self::Platform.into();
+                CollisionGroups::new(
+                    Self::Player.into(),
+                    wall_group | mob_group | item_group | platform_group,
+                )
             }
 
             GameCollisions::Environment => {
-                CollisionGroups::new(Self::Environment.into(), Self::Wall.into())
+                let wall_group: Group = Self::Wall.into();
+                let platform_group: Group = Self::Platform.into();
+                CollisionGroups::new(Self::Environment.into(), wall_group | platform_group)
             }
             GameCollisions::Mob => {
                 let wall_group: Group = Self::Wall.into();
                 let player_group: Group = Self::Player.into();
-                CollisionGroups::new(Self::Mob.into(), wall_group | player_group)
+                let projectile_group: Group = Self::Projectile.into();
+                CollisionGroups::new(
+                    Self::Mob.into(),
+                    wall_group | player_group | projectile_group,
+                )
             }
             GameCollisions::Wall => CollisionGroups::new(Self::Wall.into(), Self::All.into()),
             GameCollisions::Item => {
                 let player_group: Group = Self::Player.into();
                 let wall_group: Group = Self::Wall.into();
-                CollisionGroups::new(Self::Item.into(), wall_group | player_group)
+                let platform_group: Group = Self::Platform.into();
+                CollisionGroups::new(
+                    Self::Item.into(),
+                    wall_group | player_group | platform_group,
+                )
+            }
+            GameCollisions::Platform => {
+                CollisionGroups::new(Self::Platform.into(), Self::All.into())
+            }
+            GameCollisions::Water => CollisionGroups::new(Self::Water.into(), Self::All.into()),
+            // shots never hit their shooter
+            GameCollisions::Projectile => {
+                let wall_group: Group = Self::Wall.into();
+                let mob_group: Group = Self::Mob.into();
+                let platform_group: Group = Self::Platform.into();
+                CollisionGroups::new(
+                    Self::Projectile.into(),
+                    wall_group | mob_group | platform_group,
+                )
             }
             GameCollisions::All => CollisionGroups::new(Self::All.into(), Self::All.into()),
             GameCollisions::None => CollisionGroups::new(Self::None.into(), Self::None.into()),
@@ -80,6 +124,9 @@ impl Into<Group> for GameCollisions {
             GameCollisions::Mob => Group::GROUP_3,
             GameCollisions::Wall => Group::GROUP_4,
             GameCollisions::Item => Group::GROUP_5,
+            GameCollisions::Platform => Group::GROUP_6,
+            GameCollisions::Water => Group::GROUP_7,
+            GameCollisions::Projectile => Group::GROUP_8,
             GameCollisions::All => Group::ALL,
             GameCollisions::None => Group::NONE,
         }
@@ -121,6 +168,9 @@ impl From<IntGridCell> for SensorBundle {
                 collider: Collider::cuboid(8., 8.),
                 sensor: Sensor,
                 active_events: ActiveEvents::CONTACT_FORCE_EVENTS,
+                // the player is kinematic, those pairs are skipped by default
+                active_collision_types: ActiveCollisionTypes::default()
+                    | ActiveCollisionTypes::KINEMATIC_STATIC,
                 rotation_constraints: LockedAxes::ROTATION_LOCKED,
                 ..Default::default()
             }
@@ -136,56 +186,103 @@ pub fn detect_climb_range(
     rapier_context: Res<RapierContext>,
 ) {
     for (player, mut climber) in &mut player {
+        // `climbing` itself is toggled by the player in `movement`
         for climbable in &climbables {
             let is_intersecting = rapier_context.intersection_pair(player, climbable);
-            if is_intersecting.is_some() {
-                climber.climbing = true;
+            if is_intersecting == Some(true) {
                 climber.intersecting_climbables.insert(climbable);
             } else {
                 climber.intersecting_climbables.remove(&climbable);
-                if climber.intersecting_climbables.is_empty() {
-                    climber.climbing = false;
-                }
             }
         }
     }
 }
 
+/// Hurts the player on contact with an enemy and knocks them away from it.
+///
+/// Landing on top of an enemy stomps it instead, unless it is spiked.
 pub fn detect_player_damage(
-    mut player: Query<(&mut Health, &Transform, &Collider), With<Player>>,
-    enemies: Query<(&Transform, &Collider, &Damage), With<Enemy>>,
+    mut player: Query<
+        (
+            Entity,
+            &Damage,
+            &VerticalVelocity,
+            &mut PendingLaunch,
+            &Transform,
+            &Collider,
+            &Dash,
+        ),
+        With<Player>,
+    >,
+    enemies: Query<(Entity, &Transform, &Collider, &Damage, &Spiked), With<Enemy>>,
+    mut damage_events: EventWriter<DamageEvent>,
+    config: Res<MovementConfig>,
 ) {
-    if let Ok((mut health, player_transform, player_collider)) = player.get_single_mut() {
-        let player_collider = player_collider.as_cuboid().unwrap();
-        let player_dimentions = player_collider.half_extents() * Vec2::splat(2.5);
-        for (enemy_transform, enemy_collider, enemy_damage) in &enemies {
-            let enemy_collider = enemy_collider.as_cuboid().unwrap();
-            let enemy_dimentions = enemy_collider.half_extents() * Vec2::splat(2.5);
+    if let Ok((
+        player,
+        player_damage,
+        velocity,
+        mut launch,
+        player_transform,
+        player_collider,
+        dash,
+    )) = player.get_single_mut()
+    {
+        // a crouching player's box sits below its origin
+        let (player_center, player_half_extents) = collider_bounds(player_collider);
+        let player_position = player_transform.translation
+            + (player_center * player_transform.scale.truncate()).extend(0.);
+        let player_dimentions = player_half_extents * Vec2::splat(2.5);
+        for (enemy, enemy_transform, enemy_collider, enemy_damage, Spiked(spiked)) in &enemies {
+            let (_, enemy_half_extents) = collider_bounds(enemy_collider);
+            let enemy_dimentions = enemy_half_extents * Vec2::splat(2.5);
 
             let collision = collide(
-                player_transform.translation,
+                player_position,
                 player_dimentions,
                 enemy_transform.translation,
                 enemy_dimentions,
             );
             if let Some(collision) = collision {
-                match collision {
-                    Collision::Top => (),
-                    _ => health.0 -= enemy_damage.0,
+                if collision == Collision::Top && !spiked {
+                    // only when landing, not while jumping up past it
+                    if velocity.0 <= 0. {
+                        damage_events.send(DamageEvent {
+                            target: enemy,
+                            source: Some(player),
+                            amount: player_damage.amount,
+                            kind: player_damage.kind,
+                            knockback: None,
+                            direction: Some(Vec2::NEG_Y),
+                        });
+                        // movement owns the velocity, the bounce waits for its next tick
+                        launch.vertical_speed = Some(config.stomp_bounce_speed);
+                        break;
+                    }
+                    continue;
+                }
+                if dash.is_invulnerable() {
+                    continue;
+                }
+
+                let away = if player_transform.translation.x < enemy_transform.translation.x {
+                    -1.
+                } else {
+                    1.
                 };
+                damage_events.send(DamageEvent {
+                    target: player,
+                    source: Some(enemy),
+                    amount: enemy_damage.amount,
+                    kind: enemy_damage.kind,
+                    knockback: Some(Vec2::new(
+                        away * config.hurt_knockback_speed,
+                        config.hurt_knockback_lift,
+                    )),
+                    direction: Some(Vec2::new(away, 0.)),
+                });
+                break;
             }
         }
     }
 }
-
-pub fn ignore_gravity_if_climbing(
-    mut query: Query<(&Climber, &mut GravityScale), Changed<Climber>>,
-) {
-    for (climber, mut gravity_scale) in &mut query {
-        if climber.climbing {
-            gravity_scale.0 = 0.0;
-        } else {
-            gravity_scale.0 = 1.0;
-        }
-    }
-}
@@ -0,0 +1,148 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    utils::BoxedFuture,
+};
+use bevy_rapier2d::prelude::*;
+use serde::Deserialize;
+
+/// Tuning values for the player controller.
+///
+/// Speeds are in pixels per second, durations are in `FixedUpdate` ticks.
+#[derive(Resource, Deserialize, TypeUuid, TypePath, Clone, Debug)]
+#[uuid = "afbafbdf-a00e-4347-90ac-e2b7575a9e65"]
+pub struct MovementConfig {
+    pub run_speed: f32,
+    pub climb_speed: f32,
+    pub gravity: f32,
+    pub jump_speed: f32,
+    pub jump_cut_speed: f32,
+    pub terminal_velocity: f32,
+    pub coyote_ticks: u32,
+    pub jump_buffer_ticks: u32,
+    pub wall_slide_speed: f32,
+    pub wall_jump_horizontal_speed: f32,
+    pub wall_jump_vertical_speed: f32,
+    pub wall_jump_lock_ticks: u32,
+    pub wall_contact_grace_ticks: u32,
+    pub dash_speed: f32,
+    pub dash_ticks: u32,
+    pub dash_cooldown_ticks: u32,
+    pub dash_invulnerable_ticks: u32,
+    /// `None` allows dashing in the air without limit
+    pub max_air_dashes: Option<u32>,
+    /// How long one-way platforms are ignored after dropping through them
+    pub platform_drop_ticks: u32,
+    /// Fraction of the normal gravity applied while in water
+    pub water_gravity_scale: f32,
+    pub water_run_speed: f32,
+    pub water_terminal_velocity: f32,
+    /// Upward speed while holding jump or up in water
+    pub swim_speed: f32,
+    pub crouch_speed: f32,
+    /// Horizontal force applied to a grabbed rope segment at full input
+    pub swing_pump_force: f32,
+    /// How long the swing momentum overrides input after letting go
+    pub swing_release_ticks: u32,
+    /// Horizontal speed the player is pushed away from an attacker with
+    pub hurt_knockback_speed: f32,
+    /// Upward speed given by a hit
+    pub hurt_knockback_lift: f32,
+    /// How long the knockback overrides input
+    pub hurt_knockback_ticks: u32,
+    /// Upward speed after stomping an enemy
+    pub stomp_bounce_speed: f32,
+}
+
+impl Default for MovementConfig {
+    fn default() -> Self {
+        Self {
+            run_speed: 200.,
+            climb_speed: 120.,
+            gravity: -1000.,
+            jump_speed: 360.,
+            jump_cut_speed: 120.,
+            terminal_velocity: 480.,
+            coyote_ticks: 6,
+            jump_buffer_ticks: 6,
+            wall_slide_speed: 60.,
+            wall_jump_horizontal_speed: 240.,
+            wall_jump_vertical_speed: 330.,
+            wall_jump_lock_ticks: 10,
+            wall_contact_grace_ticks: 4,
+            dash_speed: 720.,
+            dash_ticks: 8,
+            dash_cooldown_ticks: 45,
+            dash_invulnerable_ticks: 12,
+            max_air_dashes: Some(1),
+            platform_drop_ticks: 12,
+            water_gravity_scale: 0.3,
+            water_run_speed: 120.,
+            water_terminal_velocity: 90.,
+            swim_speed: 150.,
+            crouch_speed: 80.,
+            swing_pump_force: 1500.,
+            swing_release_ticks: 12,
+            hurt_knockback_speed: 180.,
+            hurt_knockback_lift: 200.,
+            hurt_knockback_ticks: 10,
+            stomp_bounce_speed: 300.,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct MovementConfigLoader;
+
+impl AssetLoader for MovementConfigLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let config: MovementConfig = serde_json::from_slice(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(config));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["movement.json"]
+    }
+}
+
+/// Keeps the config asset alive so it can be hot reloaded
+#[derive(Resource)]
+pub struct MovementConfigHandle(pub Handle<MovementConfig>);
+
+pub fn load_movement_config(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(MovementConfigHandle(
+        asset_server.load("config/player.movement.json"),
+    ));
+}
+
+pub fn apply_movement_config(
+    mut events: EventReader<AssetEvent<MovementConfig>>,
+    configs: Res<Assets<MovementConfig>>,
+    handle: Res<MovementConfigHandle>,
+    mut movement_config: ResMut<MovementConfig>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    for event in events.iter() {
+        match event {
+            AssetEvent::Created { handle: changed } | AssetEvent::Modified { handle: changed } => {
+                if *changed != handle.0 {
+                    continue;
+                }
+                if let Some(config) = configs.get(changed) {
+                    info!("applied movement config");
+                    *movement_config = config.clone();
+                    rapier_config.gravity = Vec2::new(0., config.gravity);
+                }
+            }
+            AssetEvent::Removed { .. } => (),
+        }
+    }
+}
@@ -0,0 +1,400 @@
+use std::{fmt::Display, fs, path::Path};
+
+use bevy::{input::gamepad::GamepadSettings, prelude::*, utils::HashSet};
+use serde::{Deserialize, Serialize};
+
+pub const BINDINGS_PATH: &str = "bindings.json";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    Jump,
+    ClimbUp,
+    ClimbDown,
+    Dash,
+    UseItem,
+    Attack,
+    ToggleInventory,
+    SelectSlot(usize),
+    NextSlot,
+    PreviousSlot,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputSource {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    GamepadButton(GamepadButtonType),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Binding {
+    pub input: InputSource,
+    pub action: Action,
+}
+
+/// Missing fields fall back to their defaults, so files saved before a section existed
+/// still get it
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct InputBindings {
+    /// Keyboard and mouse bindings
+    #[serde(default = "default_bindings")]
+    pub bindings: Vec<Binding>,
+    #[serde(default = "default_gamepad_bindings")]
+    pub gamepad_bindings: Vec<Binding>,
+    /// Stick deflection below this is treated as no input
+    #[serde(default = "default_stick_deadzone")]
+    pub stick_deadzone: f32,
+}
+
+fn default_stick_deadzone() -> f32 {
+    0.2
+}
+
+/// Keeps the rescaling in `apply_deadzone` finite
+const MAX_STICK_DEADZONE: f32 = 0.95;
+
+/// Analog movement input in the `-1..=1` range, digital bindings give full deflection
+#[derive(Resource, Default, Debug)]
+pub struct MovementAxes {
+    pub horizontal: f32,
+    pub vertical: f32,
+}
+
+/// Actions pressed since the last `FixedUpdate` tick
+///
+/// A frame can run no fixed tick or several of them, so edge triggered actions read from
+/// `Input<Action>` there would be missed or seen twice.
+#[derive(Resource, Default, Debug)]
+pub struct BufferedActions {
+    pressed: HashSet<Action>,
+}
+
+impl BufferedActions {
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+}
+
+fn default_bindings() -> Vec<Binding> {
+    let keys = [
+        (KeyCode::A, Action::MoveLeft),
+        (KeyCode::Left, Action::MoveLeft),
+        (KeyCode::D, Action::MoveRight),
+        (KeyCode::Right, Action::MoveRight),
+        (KeyCode::Space, Action::Jump),
+        (KeyCode::Up, Action::Jump),
+        (KeyCode::W, Action::ClimbUp),
+        (KeyCode::S, Action::ClimbDown),
+        (KeyCode::ShiftLeft, Action::Dash),
+        (KeyCode::E, Action::UseItem),
+        (KeyCode::C, Action::ToggleInventory),
+        (KeyCode::Key1, Action::SelectSlot(0)),
+        (KeyCode::Key2, Action::SelectSlot(1)),
+        (KeyCode::Key3, Action::SelectSlot(2)),
+        (KeyCode::Key4, Action::SelectSlot(3)),
+        (KeyCode::Key5, Action::SelectSlot(4)),
+    ];
+    let mut bindings: Vec<Binding> = keys
+        .into_iter()
+        .map(|(key, action)| Binding {
+            input: InputSource::Key(key),
+            action,
+        })
+        .collect();
+    bindings.push(Binding {
+        input: InputSource::Mouse(MouseButton::Left),
+        action: Action::Attack,
+    });
+    bindings
+}
+
+fn default_gamepad_bindings() -> Vec<Binding> {
+    let gamepad_buttons = [
+        (GamepadButtonType::South, Action::Jump),
+        (GamepadButtonType::West, Action::Attack),
+        (GamepadButtonType::East, Action::Dash),
+        (GamepadButtonType::North, Action::UseItem),
+        (GamepadButtonType::Select, Action::ToggleInventory),
+        (GamepadButtonType::DPadUp, Action::ClimbUp),
+        (GamepadButtonType::DPadDown, Action::ClimbDown),
+        (GamepadButtonType::DPadLeft, Action::PreviousSlot),
+        (GamepadButtonType::DPadRight, Action::NextSlot),
+        (GamepadButtonType::LeftTrigger, Action::PreviousSlot),
+        (GamepadButtonType::RightTrigger, Action::NextSlot),
+    ];
+    gamepad_buttons
+        .into_iter()
+        .map(|(button, action)| Binding {
+            input: InputSource::GamepadButton(button),
+            action,
+        })
+        .collect()
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self {
+            bindings: default_bindings(),
+            gamepad_bindings: default_gamepad_bindings(),
+            stick_deadzone: default_stick_deadzone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum BindingsError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+}
+
+impl Display for BindingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BindingsError::Io(e) => write!(f, "Failed to access bindings file: {}", e),
+            BindingsError::Parse(e) => write!(f, "Malformed bindings file: {}", e),
+        }
+    }
+}
+
+impl InputBindings {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, BindingsError> {
+        let file = fs::read(path).map_err(BindingsError::Io)?;
+        serde_json::from_slice(&file).map_err(BindingsError::Parse)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), BindingsError> {
+        let json = serde_json::to_vec_pretty(self).map_err(BindingsError::Parse)?;
+        fs::write(path, json).map_err(BindingsError::Io)
+    }
+
+    /// Replaces every input of the same device bound to the action with a single new one
+    pub fn rebind(&mut self, action: Action, input: InputSource) {
+        let bindings = match input {
+            InputSource::GamepadButton(_) => &mut self.gamepad_bindings,
+            InputSource::Key(_) | InputSource::Mouse(_) => &mut self.bindings,
+        };
+        bindings.retain(|binding| binding.action != action);
+        bindings.push(Binding { input, action });
+    }
+}
+
+pub fn load_input_bindings(mut commands: Commands) {
+    let bindings = match InputBindings::load(BINDINGS_PATH) {
+        Ok(bindings) => bindings,
+        Err(BindingsError::Io(_)) => {
+            let bindings = InputBindings::default();
+            if let Err(e) = bindings.save(BINDINGS_PATH) {
+                warn!("{}", e);
+            }
+            bindings
+        }
+        Err(e) => {
+            warn!("{}, falling back to default bindings", e);
+            InputBindings::default()
+        }
+    };
+    commands.insert_resource(bindings);
+}
+
+/// Bevy's own axis deadzone would stack with `InputBindings::stick_deadzone`
+pub fn disable_gamepad_deadzone(mut settings: ResMut<GamepadSettings>) {
+    settings.default_axis_settings.set_deadzone_lowerbound(0.);
+    settings.default_axis_settings.set_deadzone_upperbound(0.);
+}
+
+fn apply_deadzone(value: f32, deadzone: f32) -> f32 {
+    let deadzone = deadzone.clamp(0., MAX_STICK_DEADZONE);
+    if value.abs() < deadzone {
+        0.
+    } else {
+        // rescale so the output starts from zero right at the deadzone edge
+        value.signum() * (value.abs() - deadzone) / (1. - deadzone)
+    }
+}
+
+/// Translates raw keyboard, mouse and gamepad state into `Input<Action>` and `MovementAxes`
+#[allow(clippy::too_many_arguments)]
+pub fn update_actions(
+    bindings: Res<InputBindings>,
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    mut actions: ResMut<Input<Action>>,
+    mut movement_axes: ResMut<MovementAxes>,
+) {
+    actions.clear();
+    let mut active: HashSet<Action> = bindings
+        .bindings
+        .iter()
+        .chain(&bindings.gamepad_bindings)
+        .filter(|binding| match binding.input {
+            InputSource::Key(key) => keys.pressed(key),
+            InputSource::Mouse(button) => mouse.pressed(button),
+            InputSource::GamepadButton(button_type) => gamepads
+                .iter()
+                .any(|gamepad| gamepad_buttons.pressed(GamepadButton::new(gamepad, button_type))),
+        })
+        .map(|binding| binding.action)
+        .collect();
+
+    let digital_axis = |negative: Action, positive: Action| {
+        active.contains(&positive) as i8 as f32 - active.contains(&negative) as i8 as f32
+    };
+    let mut horizontal = digital_axis(Action::MoveLeft, Action::MoveRight);
+    let mut vertical = digital_axis(Action::ClimbDown, Action::ClimbUp);
+
+    for gamepad in gamepads.iter() {
+        let stick = |axis_type| {
+            gamepad_axes
+                .get(GamepadAxis::new(gamepad, axis_type))
+                .map(|value| apply_deadzone(value, bindings.stick_deadzone))
+                .unwrap_or_default()
+        };
+        let stick_x = stick(GamepadAxisType::LeftStickX);
+        let stick_y = stick(GamepadAxisType::LeftStickY);
+        if stick_x.abs() > horizontal.abs() {
+            horizontal = stick_x;
+        }
+        if stick_y.abs() > vertical.abs() {
+            vertical = stick_y;
+        }
+    }
+
+    // sticks also drive the digital actions so edge triggered checks keep working
+    for (value, negative, positive) in [
+        (horizontal, Action::MoveLeft, Action::MoveRight),
+        (vertical, Action::ClimbDown, Action::ClimbUp),
+    ] {
+        if value > 0. {
+            active.insert(positive);
+        } else if value < 0. {
+            active.insert(negative);
+        }
+    }
+    movement_axes.horizontal = horizontal;
+    movement_axes.vertical = vertical;
+
+    let released: Vec<Action> = actions
+        .get_pressed()
+        .filter(|action| !active.contains(*action))
+        .copied()
+        .collect();
+    for action in released {
+        actions.release(action);
+    }
+    for action in active {
+        actions.press(action);
+    }
+}
+
+/// Keeps the presses of this frame until a fixed tick consumes them
+pub fn buffer_actions(actions: Res<Input<Action>>, mut buffered: ResMut<BufferedActions>) {
+    buffered.pressed.extend(actions.get_just_pressed().copied());
+}
+
+/// Runs last in `FixedUpdate` so each press is seen by exactly one tick
+pub fn clear_buffered_actions(mut buffered: ResMut<BufferedActions>) {
+    buffered.pressed.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::input::{
+        gamepad::{GamepadConnection, GamepadConnectionEvent, GamepadInfo},
+        InputPlugin, InputSystem,
+    };
+
+    use super::*;
+
+    #[test]
+    fn deadzone_ignores_small_deflection() {
+        assert_eq!(apply_deadzone(0.1, 0.2), 0.);
+        assert_eq!(apply_deadzone(-0.19, 0.2), 0.);
+    }
+
+    #[test]
+    fn deadzone_rescales_from_its_edge() {
+        assert_eq!(apply_deadzone(0.2, 0.2), 0.);
+        assert!((apply_deadzone(0.6, 0.2) - 0.5).abs() < 1e-6);
+        assert_eq!(apply_deadzone(-1., 0.2), -1.);
+    }
+
+    #[test]
+    fn full_deadzone_stays_finite() {
+        assert!(apply_deadzone(1., 1.).is_finite());
+        assert!(apply_deadzone(-0.99, 2.).is_finite());
+    }
+
+    #[test]
+    fn old_bindings_file_gets_default_gamepad_bindings() {
+        let json = r#"{ "bindings": [{ "input": { "Key": "Space" }, "action": "Jump" }] }"#;
+        let bindings: InputBindings = serde_json::from_str(json).unwrap();
+        assert_eq!(bindings.bindings.len(), 1);
+        assert_eq!(bindings.gamepad_bindings, default_gamepad_bindings());
+        assert_eq!(bindings.stick_deadzone, default_stick_deadzone());
+    }
+
+    fn app_with_gamepad() -> (App, Gamepad) {
+        let mut app = App::new();
+        app.add_plugins(InputPlugin)
+            .init_resource::<InputBindings>()
+            .init_resource::<Input<Action>>()
+            .init_resource::<MovementAxes>()
+            .add_systems(PreUpdate, update_actions.after(InputSystem));
+        let gamepad = Gamepad::new(0);
+        app.world.send_event(GamepadConnectionEvent::new(
+            gamepad,
+            GamepadConnection::Connected(GamepadInfo {
+                name: "test pad".to_owned(),
+            }),
+        ));
+        app.update();
+        (app, gamepad)
+    }
+
+    #[test]
+    fn gamepad_button_drives_bound_action() {
+        let (mut app, gamepad) = app_with_gamepad();
+        let south = GamepadButton::new(gamepad, GamepadButtonType::South);
+
+        app.world
+            .resource_mut::<Input<GamepadButton>>()
+            .press(south);
+        app.update();
+        let actions = app.world.resource::<Input<Action>>();
+        assert!(actions.just_pressed(Action::Jump));
+
+        app.update();
+        let actions = app.world.resource::<Input<Action>>();
+        assert!(actions.pressed(Action::Jump));
+        assert!(!actions.just_pressed(Action::Jump));
+
+        app.world
+            .resource_mut::<Input<GamepadButton>>()
+            .release(south);
+        app.update();
+        let actions = app.world.resource::<Input<Action>>();
+        assert!(actions.just_released(Action::Jump));
+    }
+
+    #[test]
+    fn stick_drives_axes_and_digital_actions() {
+        let (mut app, gamepad) = app_with_gamepad();
+
+        app.world
+            .resource_mut::<Axis<GamepadAxis>>()
+            .set(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX), 0.6);
+        app.update();
+        let axes = app.world.resource::<MovementAxes>();
+        assert!((axes.horizontal - 0.5).abs() < 1e-6);
+        assert_eq!(axes.vertical, 0.);
+        assert!(app
+            .world
+            .resource::<Input<Action>>()
+            .pressed(Action::MoveRight));
+    }
+}
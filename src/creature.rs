@@ -1,19 +1,32 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 use bevy_rapier2d::prelude::*;
 
 use crate::{
+    animation::{AnimationBundle, AnimationState},
     collisions::ColliderBundle,
+    config::MovementConfig,
     enemy::Loot,
     items::EntitiesResource,
     particles::spawn_splash_particles,
-    player::{AnimationBundle, MoveDirection},
+    player::{HorizontalImpulse, MoveDirection, PendingLaunch},
+    status::StatusEffects,
 };
 
 #[derive(Component)]
 pub struct Health(pub isize);
 
+/// Damage a creature or projectile deals on contact
 #[derive(Component, Default, Clone, Debug)]
-pub struct Damage(pub isize);
+pub struct Damage {
+    pub amount: isize,
+    pub kind: DamageKind,
+}
+
+impl Damage {
+    pub fn new(amount: isize, kind: DamageKind) -> Self {
+        Self { amount, kind }
+    }
+}
 
 impl Default for Health {
     fn default() -> Self {
@@ -21,10 +34,153 @@ impl Default for Health {
     }
 }
 
+impl Health {
+    /// Heals without going past the maximum
+    pub fn heal(&mut self, amount: isize, max: &MaxHealth) {
+        self.0 = (self.0 + amount).min(max.0);
+    }
+}
+
+/// Health a creature can be healed up to
+#[derive(Component)]
+pub struct MaxHealth(pub isize);
+
+impl Default for MaxHealth {
+    fn default() -> Self {
+        Self(100)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DamageKind {
+    #[default]
+    Physical,
+    Fire,
+    Poison,
+    Drowning,
+}
+
+/// Damage multiplier per kind, 0 is immune, below 1 resists and above 1 is a weakness
+#[derive(Component, Default, Clone, Debug)]
+pub struct Resistances(pub HashMap<DamageKind, f32>);
+
+impl Resistances {
+    /// Kinds without an entry deal full damage
+    pub fn multiplier(&self, kind: DamageKind) -> f32 {
+        self.0.get(&kind).copied().unwrap_or(1.)
+    }
+
+    pub fn apply(&self, amount: isize, kind: DamageKind) -> isize {
+        (amount as f32 * self.multiplier(kind)).round() as isize
+    }
+}
+
+/// Request to hurt a creature, `apply_damage` is the only system lowering `Health`
+#[derive(Event, Clone, Debug)]
+pub struct DamageEvent {
+    pub target: Entity,
+    /// Attacker, `None` for damage from the environment or from items
+    pub source: Option<Entity>,
+    pub amount: isize,
+    pub kind: DamageKind,
+    /// Velocity the target is pushed away with
+    pub knockback: Option<Vec2>,
+    /// Normalized direction the blow came in, the splash of the hit flies along it
+    pub direction: Option<Vec2>,
+}
+
+/// Pushes a creature away after a hit, it doesn't patrol meanwhile
+#[derive(Component, Debug)]
+pub struct Knockback {
+    pub velocity: Vec2,
+    pub timer: Timer,
+}
+
+const KNOCKBACK_SECONDS: f32 = 0.2;
+/// Impulse on each particle of a splash thrown by a blow with a direction
+const SPLASH_IMPULSE: f32 = 400.;
+
+/// Seconds after a hit during which no damage is taken
+const INVULNERABILITY_SECONDS: f32 = 1.;
+/// Part of the invulnerability the creature plays its hurt animation for
+const HURT_SECONDS: f32 = 0.3;
+const FLASH_SECONDS: f32 = 0.08;
+
+/// Started by every hit, the sprite flashes while it runs
+#[derive(Component, Debug)]
+pub struct Invulnerability {
+    pub timer: Timer,
+    pub flash_timer: Timer,
+}
+
+impl Default for Invulnerability {
+    fn default() -> Self {
+        Self::new(INVULNERABILITY_SECONDS)
+    }
+}
+
+impl Invulnerability {
+    /// Lasts the given amount of seconds after every hit
+    pub fn new(seconds: f32) -> Self {
+        let mut timer = Timer::from_seconds(seconds, TimerMode::Once);
+        timer.tick(timer.duration());
+        Self {
+            timer,
+            flash_timer: Timer::from_seconds(FLASH_SECONDS, TimerMode::Repeating),
+        }
+    }
+
+    pub fn start(&mut self) {
+        self.timer.reset();
+        self.flash_timer.reset();
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.timer.finished()
+    }
+
+    pub fn is_hurt(&self) -> bool {
+        self.is_active() && self.timer.elapsed_secs() < HURT_SECONDS
+    }
+}
+
+/// Spent on spells, refills over time
+#[derive(Component, Debug)]
+pub struct Mana {
+    pub current: f32,
+    pub max: f32,
+    /// Mana regained per second
+    pub regen: f32,
+}
+
+impl Default for Mana {
+    fn default() -> Self {
+        Self {
+            current: 100.,
+            max: 100.,
+            regen: 10.,
+        }
+    }
+}
+
+impl Mana {
+    /// Takes the cost if there is enough mana left
+    pub fn spend(&mut self, cost: f32) -> bool {
+        if self.current < cost {
+            return false;
+        }
+        self.current -= cost;
+        true
+    }
+}
+
 #[derive(Bundle, Default)]
 pub struct CreatureBundle {
     pub health: Health,
+    pub max_health: MaxHealth,
     pub damage: Damage,
+    pub resistances: Resistances,
+    pub status_effects: StatusEffects,
     pub sprite: SpriteSheetBundle,
     pub move_direction: MoveDirection,
     pub character_controller: KinematicCharacterController,
@@ -52,3 +208,259 @@ pub fn detect_creature_death(
         }
     }
 }
+
+pub fn regenerate_mana(mut creatures: Query<&mut Mana>, time: Res<Time>) {
+    for mut mana in &mut creatures {
+        if mana.current < mana.max {
+            mana.current = (mana.current + mana.regen * time.delta_seconds()).min(mana.max);
+        }
+    }
+}
+
+pub fn tick_invulnerability(
+    mut creatures: Query<(&mut Invulnerability, &mut TextureAtlasSprite)>,
+    time: Res<Time>,
+) {
+    for (mut invulnerability, mut sprite) in &mut creatures {
+        if !invulnerability.is_active() {
+            if sprite.color.a() < 1. {
+                sprite.color.set_a(1.);
+            }
+            continue;
+        }
+        invulnerability.timer.tick(time.delta());
+        if invulnerability
+            .flash_timer
+            .tick(time.delta())
+            .just_finished()
+        {
+            let alpha = if sprite.color.a() < 1. { 1. } else { 0.3 };
+            sprite.color.set_a(alpha);
+        }
+    }
+}
+
+/// Animation state label of creatures that were just hit
+pub const HURT: &str = "hurt";
+
+pub fn hurt_animation_state(mut creatures: Query<(&Invulnerability, &mut AnimationState)>) {
+    for (invulnerability, mut state) in &mut creatures {
+        state.set(HURT, invulnerability.is_hurt());
+    }
+}
+
+/// Resolves damage events, attacks are ignored while the target is invulnerable
+/// and start its invulnerability otherwise
+pub fn apply_damage(
+    mut commands: Commands,
+    mut events: EventReader<DamageEvent>,
+    mut targets: Query<(
+        &mut Health,
+        Option<&Resistances>,
+        &Transform,
+        Option<&mut Invulnerability>,
+        Option<&mut PendingLaunch>,
+    )>,
+    config: Res<MovementConfig>,
+) {
+    for event in events.iter() {
+        let Ok((mut health, resistances, transform, invulnerability, launch)) =
+            targets.get_mut(event.target)
+        else {
+            continue;
+        };
+        let amount = resistances
+            .map(|resistances| resistances.apply(event.amount, event.kind))
+            .unwrap_or(event.amount);
+        // immune targets aren't even flinching
+        if amount == 0 {
+            continue;
+        }
+        if let (Some(_), Some(mut invulnerability)) = (event.source, invulnerability) {
+            if invulnerability.is_active() {
+                continue;
+            }
+            invulnerability.start();
+        }
+        health.0 -= amount;
+
+        if let Some(knockback) = event.knockback {
+            match launch {
+                // the player moves through its controller in the fixed step instead
+                Some(mut launch) => {
+                    launch.impulse = Some(HorizontalImpulse {
+                        speed: knockback.x,
+                        ticks: config.hurt_knockback_ticks,
+                    });
+                    launch.vertical_speed = Some(knockback.y);
+                }
+                None => {
+                    commands.entity(event.target).insert(Knockback {
+                        velocity: knockback,
+                        timer: Timer::from_seconds(KNOCKBACK_SECONDS, TimerMode::Once),
+                    });
+                }
+            }
+        }
+        if event.kind != DamageKind::Drowning {
+            let impulse = event.direction.map(|direction| ExternalImpulse {
+                impulse: direction * SPLASH_IMPULSE,
+                torque_impulse: 0.,
+            });
+            spawn_splash_particles(&mut commands, 10, *transform, impulse);
+        }
+    }
+}
+
+pub fn apply_knockback(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut creatures: Query<(Entity, &mut Velocity, &mut Knockback)>,
+) {
+    for (entity, mut velocity, mut knockback) in &mut creatures {
+        if knockback.timer.tick(time.delta()).finished() {
+            velocity.linvel = Vec2::ZERO;
+            commands.entity(entity).remove::<Knockback>();
+        } else {
+            velocity.linvel = knockback.velocity;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_event::<DamageEvent>()
+            .init_resource::<MovementConfig>()
+            .init_resource::<EntitiesResource>()
+            .add_systems(Update, (apply_damage, detect_creature_death).chain());
+        app
+    }
+
+    fn hit(target: Entity, source: Option<Entity>, amount: isize, kind: DamageKind) -> DamageEvent {
+        DamageEvent {
+            target,
+            source,
+            amount,
+            kind,
+            knockback: None,
+            direction: None,
+        }
+    }
+
+    fn health(app: &App, creature: Entity) -> isize {
+        app.world.get::<Health>(creature).unwrap().0
+    }
+
+    #[test]
+    fn attacks_are_ignored_while_invulnerable() {
+        let mut app = app();
+        let attacker = app.world.spawn_empty().id();
+        let creature = app
+            .world
+            .spawn((
+                Health(100),
+                Invulnerability::default(),
+                Transform::default(),
+            ))
+            .id();
+
+        app.world
+            .send_event(hit(creature, Some(attacker), 10, DamageKind::Physical));
+        app.world
+            .send_event(hit(creature, Some(attacker), 10, DamageKind::Physical));
+        app.update();
+        assert_eq!(health(&app, creature), 90);
+
+        // damage from the environment goes through
+        app.world
+            .send_event(hit(creature, None, 10, DamageKind::Poison));
+        app.update();
+        assert_eq!(health(&app, creature), 80);
+    }
+
+    #[test]
+    fn resistances_scale_damage() {
+        let mut app = app();
+        let attacker = app.world.spawn_empty().id();
+        let resistances = Resistances(HashMap::from([
+            (DamageKind::Fire, 0.5),
+            (DamageKind::Poison, 0.),
+        ]));
+        let creature = app
+            .world
+            .spawn((
+                Health(100),
+                resistances,
+                Invulnerability::default(),
+                Transform::default(),
+            ))
+            .id();
+
+        app.world
+            .send_event(hit(creature, Some(attacker), 10, DamageKind::Poison));
+        app.update();
+        assert_eq!(health(&app, creature), 100);
+        let invulnerability = app.world.get::<Invulnerability>(creature).unwrap();
+        assert!(
+            !invulnerability.is_active(),
+            "immune hits don't start i-frames"
+        );
+
+        app.world
+            .send_event(hit(creature, Some(attacker), 10, DamageKind::Fire));
+        app.update();
+        assert_eq!(health(&app, creature), 95);
+    }
+
+    #[test]
+    fn lethal_damage_despawns_the_creature() {
+        let mut app = app();
+        let creature = app.world.spawn((Health(5), Transform::default())).id();
+
+        app.world
+            .send_event(hit(creature, None, 10, DamageKind::Physical));
+        app.update();
+        assert!(app.world.get_entity(creature).is_none());
+    }
+
+    #[test]
+    fn knockback_pushes_creatures_without_a_controller() {
+        let mut app = app();
+        let creature = app.world.spawn((Health(100), Transform::default())).id();
+
+        app.world.send_event(DamageEvent {
+            knockback: Some(Vec2::new(100., 0.)),
+            direction: Some(Vec2::X),
+            ..hit(creature, None, 10, DamageKind::Physical)
+        });
+        app.update();
+        let knockback = app.world.get::<Knockback>(creature).unwrap();
+        assert_eq!(knockback.velocity, Vec2::new(100., 0.));
+    }
+
+    #[test]
+    fn knockback_on_the_player_waits_for_movement() {
+        let mut app = app();
+        let player = app
+            .world
+            .spawn((Health(100), PendingLaunch::default(), Transform::default()))
+            .id();
+
+        app.world.send_event(DamageEvent {
+            knockback: Some(Vec2::new(-180., 200.)),
+            ..hit(player, None, 10, DamageKind::Physical)
+        });
+        app.update();
+        let launch = app.world.get::<PendingLaunch>(player).unwrap();
+        assert_eq!(launch.vertical_speed, Some(200.));
+        assert_eq!(
+            launch.impulse.as_ref().map(|impulse| impulse.speed),
+            Some(-180.)
+        );
+        assert!(app.world.get::<Knockback>(player).is_none());
+    }
+}
@@ -4,7 +4,11 @@ use bevy_rapier2d::prelude::*;
 
 use crate::{
     collisions::GameCollisions,
-    creature::{CreatureBundle, Damage, Health},
+    creature::{
+        CreatureBundle, Damage, DamageKind, Health, Invulnerability, Knockback, MaxHealth,
+        Resistances,
+    },
+    status::StatusEffects,
 };
 
 #[derive(Component, Default)]
@@ -13,6 +17,13 @@ pub struct Enemy;
 #[derive(Component, Default)]
 pub struct Loot(pub Vec<String>);
 
+/// Spiked enemies can't be stomped, landing on them hurts the player instead
+#[derive(Component, Default)]
+pub struct Spiked(pub bool);
+
+/// Seconds an enemy ignores attacks after a hit, so a stomp lands once
+const ENEMY_INVULNERABILITY_SECONDS: f32 = 0.3;
+
 #[derive(Default)]
 enum EnemyType {
     #[default]
@@ -38,6 +49,8 @@ pub struct EnemyBundle {
     pub creature_bundle: CreatureBundle,
     pub patrol: Patrol,
     pub loot: Loot,
+    pub spiked: Spiked,
+    pub invulnerability: Invulnerability,
 }
 
 impl LdtkEntity for EnemyBundle {
@@ -77,9 +90,32 @@ impl LdtkEntity for EnemyBundle {
             })
             .unwrap_or_default();
 
+        let spiked = *entity_instance.get_bool_field("Spiked").unwrap_or(&false);
+        let resistances = [
+            ("Physical", DamageKind::Physical),
+            ("Fire", DamageKind::Fire),
+            ("Poison", DamageKind::Poison),
+        ]
+        .into_iter()
+        .filter_map(|(field, kind)| {
+            entity_instance
+                .get_float_field(field)
+                .ok()
+                .map(|multiplier| (kind, *multiplier))
+        })
+        .collect();
+
         let texture_atlas_handle = texture_atlases.add(texture_atlas);
         let sprite_sheet_bundle = SpriteSheetBundle {
-            sprite: TextureAtlasSprite::default(),
+            sprite: TextureAtlasSprite {
+                // tinted so spiked enemies can be told apart
+                color: if spiked {
+                    Color::rgb(1., 0.6, 0.6)
+                } else {
+                    Color::WHITE
+                },
+                ..Default::default()
+            },
             texture_atlas: texture_atlas_handle,
             transform: Transform::from_xyz(
                 (entity_instance.px.x + layer_instance.px_total_offset_x) as f32,
@@ -92,7 +128,9 @@ impl LdtkEntity for EnemyBundle {
             enemy: Enemy,
             creature_bundle: CreatureBundle {
                 health: Health(100),
-                damage: Damage(5),
+                max_health: MaxHealth(100),
+                damage: Damage::new(5, DamageKind::Physical),
+                resistances: Resistances(resistances),
                 sprite: sprite_sheet_bundle,
                 collider_bundle: entity_instance.into(),
                 character_controller: KinematicCharacterController {
@@ -102,7 +140,9 @@ impl LdtkEntity for EnemyBundle {
                 ..Default::default()
             },
             loot: Loot(loot),
+            spiked: Spiked(spiked),
             patrol: Patrol::from((entity_instance, layer_instance)),
+            invulnerability: Invulnerability::new(ENEMY_INVULNERABILITY_SECONDS),
         }
     }
 }
@@ -165,7 +205,8 @@ pub fn spawn_enemy(mut commands: Commands) {
                 ..Default::default()
             },
             health: Health(100),
-            damage: Damage(10),
+            max_health: MaxHealth(100),
+            damage: Damage::new(10, DamageKind::Physical),
             ..Default::default()
         }),
         EnemyType::Strong => entity.insert(CreatureBundle {
@@ -173,7 +214,8 @@ pub fn spawn_enemy(mut commands: Commands) {
                 ..Default::default()
             },
             health: Health(150),
-            damage: Damage(30),
+            max_health: MaxHealth(150),
+            damage: Damage::new(30, DamageKind::Physical),
             ..Default::default()
         }),
         EnemyType::Boss => entity.insert(CreatureBundle {
@@ -181,21 +223,33 @@ pub fn spawn_enemy(mut commands: Commands) {
                 ..Default::default()
             },
             health: Health(300),
-            damage: Damage(50),
+            max_health: MaxHealth(300),
+            damage: Damage::new(50, DamageKind::Physical),
             ..Default::default()
         }),
     };
     commands.spawn((Enemy, Patrol::default()));
 }
 
-pub fn patrol(mut query: Query<(&mut Transform, &mut Velocity, &mut Patrol)>) {
-    for (mut transform, mut velocity, mut patrol) in &mut query {
+pub fn patrol(
+    mut query: Query<
+        (
+            &mut Transform,
+            &mut Velocity,
+            &mut Patrol,
+            Option<&StatusEffects>,
+        ),
+        Without<Knockback>,
+    >,
+) {
+    for (mut transform, mut velocity, mut patrol, status_effects) in &mut query {
         if patrol.points.len() <= 1 {
             continue;
         }
+        let speed = 75. * status_effects.map_or(1., StatusEffects::speed_multiplier);
 
         let mut new_velocity =
-            (patrol.points[patrol.index] - transform.translation.truncate()).normalize() * 75.;
+            (patrol.points[patrol.index] - transform.translation.truncate()).normalize() * speed;
 
         if new_velocity.dot(velocity.linvel) < 0. {
             if patrol.index == 0 {
@@ -213,8 +267,9 @@ pub fn patrol(mut query: Query<(&mut Transform, &mut Velocity, &mut Patrol)>) {
                 patrol.index -= 1;
             }
 
-            new_velocity =
-                (patrol.points[patrol.index] - transform.translation.truncate()).normalize() * 75.;
+            new_velocity = (patrol.points[patrol.index] - transform.translation.truncate())
+                .normalize()
+                * speed;
         }
 
         velocity.linvel = new_velocity;
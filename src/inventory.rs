@@ -3,12 +3,14 @@ use std::fmt::Display;
 use bevy::{prelude::*, ui::RelativeCursorPosition, utils::HashMap};
 
 use crate::{
-    creature::Health,
-    items::{Consumable, ItemType},
+    controls::Action,
+    creature::{DamageEvent, DamageKind, Health, MaxHealth},
+    items::{Consumable, ItemType, Weapon},
     player::Player,
+    status::{StatusEffect, StatusEvent},
     ui::{
         ExpandedInventoryIndicator, InventoryAmountIndicator, InventoryImageIndicator,
-        InventorySlot, SELECTED_COLOR, TRANSPARENT,
+        InventorySlot, HOTBAR_SLOTS, SELECTED_COLOR, TRANSPARENT,
     },
 };
 
@@ -128,6 +130,36 @@ impl Inventory {
         }
     }
 
+    /// Amount of the named item across all slots
+    pub fn count(&self, name: &str) -> u32 {
+        self.items
+            .values()
+            .filter(|item| item.name == name)
+            .map(|item| item.amount)
+            .sum()
+    }
+
+    /// Removes one of the named item, returns whether there was one
+    pub fn take(&mut self, name: &str) -> bool {
+        let slot = self
+            .items
+            .iter()
+            .find(|(_, item)| item.name == name)
+            .map(|(slot, _)| *slot);
+        if let Some(slot) = slot {
+            self.remove_item(slot);
+        }
+        slot.is_some()
+    }
+
+    /// Weapon in the selected slot, if the slot holds one
+    pub fn selected_weapon(&self) -> Option<Weapon> {
+        match self.items.get(&self.selected_slot)?.item_type {
+            ItemType::Weapon(weapon) => Some(weapon),
+            _ => None,
+        }
+    }
+
     pub fn move_slot(&mut self, from: usize, to: usize) {
         if from == to {
             return;
@@ -141,20 +173,33 @@ impl Inventory {
 }
 
 pub fn consume_selected_item(
-    input: Res<Input<KeyCode>>,
+    actions: Res<Input<Action>>,
     mut inventory: ResMut<Inventory>,
-    mut player_health: Query<&mut Health, With<Player>>,
+    mut player_health: Query<(Entity, &mut Health, &MaxHealth), With<Player>>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut status_events: EventWriter<StatusEvent>,
 ) {
-    if input.is_changed() {
-        if input.just_pressed(KeyCode::Period) {
-            if let Ok(mut health) = player_health.get_single_mut() {
+    if actions.is_changed() {
+        if actions.just_pressed(Action::UseItem) {
+            if let Ok((player, mut health, max_health)) = player_health.get_single_mut() {
                 let selected_slot = inventory.selected_slot.clone();
                 let val = inventory.items.get_mut(&selected_slot);
                 if let Some(item) = val {
                     if let ItemType::Consumable(effect) = &item.item_type {
                         match effect {
-                            Consumable::Heal(amount) => health.0 += *amount as isize,
-                            Consumable::Damage(amount) => health.0 -= *amount as isize,
+                            Consumable::Heal(amount) => health.heal(*amount as isize, max_health),
+                            Consumable::Damage(amount) => damage_events.send(DamageEvent {
+                                target: player,
+                                source: None,
+                                amount: *amount as isize,
+                                kind: DamageKind::Poison,
+                                knockback: None,
+                                direction: None,
+                            }),
+                            Consumable::Status(kind, seconds) => status_events.send(StatusEvent {
+                                target: player,
+                                effect: StatusEffect::new(*kind, *seconds as f32),
+                            }),
                         }
                         inventory.remove_item(selected_slot);
                     };
@@ -195,11 +240,11 @@ pub fn update_inventory_ui(
 }
 
 pub fn toggle_inventory(
-    input: Res<Input<KeyCode>>,
+    actions: Res<Input<Action>>,
     mut expanded_inventory: Query<&mut Visibility, With<ExpandedInventoryIndicator>>,
 ) {
-    if input.is_changed() {
-        if input.just_pressed(KeyCode::C) {
+    if actions.is_changed() {
+        if actions.just_pressed(Action::ToggleInventory) {
             if let Ok(mut visibility) = expanded_inventory.get_single_mut() {
                 match visibility.as_mut() {
                     Visibility::Hidden => *visibility = Visibility::Visible,
@@ -211,15 +256,18 @@ pub fn toggle_inventory(
     }
 }
 
-pub fn update_selected_slot(input: Res<Input<KeyCode>>, mut inventory: ResMut<Inventory>) {
-    if input.is_changed() {
-        for press in input.get_just_pressed() {
+pub fn update_selected_slot(actions: Res<Input<Action>>, mut inventory: ResMut<Inventory>) {
+    if actions.is_changed() {
+        for press in actions.get_just_pressed() {
             match press {
-                KeyCode::Key1 => inventory.selected_slot = 0,
-                KeyCode::Key2 => inventory.selected_slot = 1,
-                KeyCode::Key3 => inventory.selected_slot = 2,
-                KeyCode::Key4 => inventory.selected_slot = 3,
-                KeyCode::Key5 => inventory.selected_slot = 4,
+                Action::SelectSlot(slot) => inventory.selected_slot = *slot,
+                Action::NextSlot => {
+                    inventory.selected_slot = (inventory.selected_slot + 1) % HOTBAR_SLOTS
+                }
+                Action::PreviousSlot => {
+                    inventory.selected_slot =
+                        (inventory.selected_slot + HOTBAR_SLOTS - 1) % HOTBAR_SLOTS
+                }
                 _ => (),
             }
         }
@@ -255,6 +303,7 @@ pub fn slot_buttons_system(
     mut inventory: ResMut<Inventory>,
     mut drag_state: ResMut<InventoryDragState>,
     mut input: ResMut<Input<MouseButton>>,
+    mut actions: ResMut<Input<Action>>,
 ) {
     let is_released = input.just_released(MouseButton::Left);
     for (InventorySlot(slot), interaction, cursor_position) in &interaction_query {
@@ -273,6 +322,7 @@ pub fn slot_buttons_system(
                 drag_state.is_dragging = true;
             }
             input.clear_just_pressed(MouseButton::Left);
+            actions.clear_just_pressed(Action::Attack);
         }
     }
 }
@@ -10,10 +10,11 @@ use crate::{
     collisions::{ColliderBundle, GameCollisions},
     inventory::{Inventory, InventoryItem},
     player::Player,
+    status::StatusKind,
 };
 
 #[derive(Component, Default)]
-pub struct Item(String);
+pub struct Item(pub String);
 
 #[derive(Bundle, Default)]
 pub struct ItemBundle {
@@ -75,11 +76,21 @@ pub fn generate_item_type(
             Some(ItemType::Consumable(match field.identifier.as_str() {
                 "Heal" => Consumable::Heal(val),
                 "Damage" => Consumable::Damage(val),
-                _ => panic!("field on consumable should be either Heal or Damage"),
+                identifier => Consumable::Status(StatusKind::from_identifier(identifier)?, val),
             }))
         }
         "Material" => Some(ItemType::CraftMaterial),
-        "Weapon" => todo!(),
+        "Weapon" => {
+            let field = &defenitions.first()?;
+            let override_value = field.default_override.clone()?;
+            let arr = override_value.get("params")?;
+            let value = arr.get(0)?;
+            let damage = value.as_u64()? as usize;
+            Some(ItemType::Weapon(Weapon {
+                weapon_type: WeaponType::from_identifier(&field.identifier)?,
+                damage,
+            }))
+        }
         _ => panic!("all tags should be covered"),
     };
 }
@@ -167,13 +178,37 @@ impl LdtkEntity for ItemBundle {
                         match field.identifier.as_str() {
                             "Damage" => ItemType::Consumable(Consumable::Damage(value)),
                             "Heal" => ItemType::Consumable(Consumable::Heal(value)),
-                            _ => panic!("unknown field"),
+                            identifier => match StatusKind::from_identifier(identifier) {
+                                Some(kind) => ItemType::Consumable(Consumable::Status(kind, value)),
+                                // the level still loads, the item just can't be consumed
+                                None => ItemType::CraftMaterial,
+                            },
                         }
                     }
                     _ => panic!("field shoud be typeof integer"),
                 }
             }
             "Material" => ItemType::CraftMaterial,
+            "Weapon" => {
+                let field = &entity_instance
+                    .field_instances
+                    .first()
+                    .expect("to have at least one field");
+
+                match field.value {
+                    FieldValue::Int(value) => {
+                        match WeaponType::from_identifier(&field.identifier) {
+                            Some(weapon_type) => ItemType::Weapon(Weapon {
+                                weapon_type,
+                                damage: value.expect("to be non empty") as usize,
+                            }),
+                            // the level still loads, the item just can't be wielded
+                            None => ItemType::CraftMaterial,
+                        }
+                    }
+                    _ => panic!("field shoud be typeof integer"),
+                }
+            }
             _ => panic!("all tags should be covered"),
         };
 
@@ -229,6 +264,8 @@ impl Default for ItemType {
 pub enum Consumable {
     Heal(u32),
     Damage(u32),
+    /// Timed effect lasting the given amount of seconds
+    Status(StatusKind, u32),
 }
 impl Default for Consumable {
     fn default() -> Self {
@@ -239,13 +276,27 @@ impl Default for Consumable {
 #[derive(Default, Debug)]
 pub struct CraftMaterial;
 
-#[derive(Default, Clone, Copy, Debug)]
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum WeaponType {
     #[default]
     Sword,
     Bow,
 }
 
+impl WeaponType {
+    /// Weapon named by the field of a weapon entity, unknown names are logged
+    pub fn from_identifier(identifier: &str) -> Option<Self> {
+        match identifier {
+            "Sword" => Some(Self::Sword),
+            "Bow" => Some(Self::Bow),
+            _ => {
+                warn!("skipping weapon {identifier}, its field should be either Sword or Bow");
+                None
+            }
+        }
+    }
+}
+
 #[derive(Component, Default, Clone, Copy, Debug)]
 pub struct Weapon {
     pub weapon_type: WeaponType,
@@ -270,8 +321,10 @@ pub fn pickup_item(
     // TODO: make generic function to check collisions
     if let Ok(player_entity) = player.get_single() {
         for (entity, sprite, item_type, Item(item_name)) in &items {
-            let contact = rapier_context.contact_pair(player_entity, entity);
-            if contact.is_some() {
+            // items stuck in walls are sensors
+            let contact = rapier_context.contact_pair(player_entity, entity).is_some()
+                || rapier_context.intersection_pair(player_entity, entity) == Some(true);
+            if contact {
                 if let Err(e) = inventory.add_item(InventoryItem {
                     icon: sprite.clone(),
                     name: item_name.clone(),
@@ -1,7 +1,12 @@
 use bevy::{prelude::*, utils::HashSet};
 use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
 
-use crate::collisions::SensorBundle;
+use crate::{
+    animation::AnimationState,
+    collisions::{GameCollisions, SensorBundle},
+    map::{tiles_by_level, OneWayPlatform, PLATFORM_THICKNESS},
+};
 
 #[derive(Clone, Eq, PartialEq, Debug, Default, Component)]
 pub struct Climber {
@@ -12,6 +17,10 @@ pub struct Climber {
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
 pub struct Climbable;
 
+/// One-way surface on the top cell of a ladder column
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct LadderTop;
+
 #[derive(Clone, Debug, Default, Bundle)]
 pub struct LadderBundle {
     pub sensor_bundle: SensorBundle,
@@ -26,3 +35,66 @@ impl LdtkIntCell for LadderBundle {
         }
     }
 }
+
+/// Spawns a one-way platform over the top cell of every ladder column,
+/// so the player can stand on the ladder and climb down from there
+pub fn spawn_ladder_tops(
+    mut commands: Commands,
+    ladder_query: Query<(&GridCoords, &Parent), Added<Climbable>>,
+    parent_query: Query<&Parent, Without<Climbable>>,
+    level_query: Query<(Entity, &Handle<LdtkLevel>)>,
+    levels: Res<Assets<LdtkLevel>>,
+) {
+    if ladder_query.is_empty() {
+        return;
+    }
+    let level_to_ladder_locations = tiles_by_level(ladder_query.iter(), &parent_query);
+
+    level_query.for_each(|(level_entity, level_handle)| {
+        if let Some(level_ladders) = level_to_ladder_locations.get(&level_entity) {
+            let level = levels
+                .get(level_handle)
+                .expect("Level should be loaded by this point");
+            let grid_size = level
+                .level
+                .layer_instances
+                .as_ref()
+                .expect("Level asset should have layers")[0]
+                .grid_size as f32;
+            let collision_groups: CollisionGroups = GameCollisions::Platform.into();
+
+            commands.entity(level_entity).with_children(|level| {
+                let tops = level_ladders.iter().filter(|coords| {
+                    !level_ladders.contains(&GridCoords {
+                        x: coords.x,
+                        y: coords.y + 1,
+                    })
+                });
+                for top in tops {
+                    level.spawn((
+                        Collider::cuboid(grid_size / 2., PLATFORM_THICKNESS / 2.),
+                        OneWayPlatform,
+                        LadderTop,
+                        collision_groups,
+                        RigidBody::Fixed,
+                        Friction::new(1.0),
+                        TransformBundle::from_transform(Transform::from_xyz(
+                            (top.x as f32 + 0.5) * grid_size,
+                            (top.y + 1) as f32 * grid_size - PLATFORM_THICKNESS / 2.,
+                            0.,
+                        )),
+                    ));
+                }
+            });
+        }
+    });
+}
+
+/// Animation state label of climbers on a ladder
+pub const CLIMBING: &str = "climbing";
+
+pub fn climbing_animation_state(mut climbers: Query<(&Climber, &mut AnimationState)>) {
+    for (climber, mut state) in &mut climbers {
+        state.set(CLIMBING, climber.climbing);
+    }
+}
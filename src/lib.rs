@@ -1,7 +1,11 @@
 #![feature(const_trait_impl)]
 pub mod ai;
+pub mod animation;
+pub mod aseprite;
 pub mod camera;
 pub mod collisions;
+pub mod config;
+pub mod controls;
 pub mod creature;
 pub mod enemy;
 pub mod inventory;
@@ -9,8 +13,12 @@ pub mod items;
 pub mod ladder;
 pub mod map;
 pub mod particles;
+pub mod platform;
 pub mod player;
 pub mod projectile;
+pub mod rope;
+pub mod status;
 pub mod ui;
 pub mod utils;
+pub mod water;
 pub mod weapon;
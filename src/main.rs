@@ -1,25 +1,47 @@
-use bevy::{asset::ChangeWatcher, prelude::*, window::close_on_esc};
+use bevy::{asset::ChangeWatcher, input::InputSystem, prelude::*, window::close_on_esc};
 use bevy_ecs_ldtk::prelude::*;
 use bevy_rapier2d::prelude::*;
 use platformer::{
-    camera::{camera_fit_inside_current_level, setup_camera},
+    animation::{animate, select_animation},
+    aseprite::{apply_aseprite_sheets, AsepriteLoader, AsepriteSheet},
+    camera::{
+        camera_fit_inside_current_level, setup_camera, update_cursor_world_position,
+        CursorWorldPosition,
+    },
     collisions::{detect_climb_range, detect_player_damage},
-    creature::detect_creature_death,
+    config::{apply_movement_config, load_movement_config, MovementConfig, MovementConfigLoader},
+    controls::{
+        buffer_actions, clear_buffered_actions, disable_gamepad_deadzone, load_input_bindings,
+        update_actions, Action, BufferedActions, MovementAxes,
+    },
+    creature::{
+        apply_damage, apply_knockback, detect_creature_death, hurt_animation_state,
+        regenerate_mana, tick_invulnerability, DamageEvent,
+    },
     enemy::{patrol, EnemyBundle},
     inventory::{
         consume_selected_item, move_drag_objects, slot_buttons_system, toggle_inventory,
         update_inventory_ui, update_selected_slot, Inventory, InventoryDragState,
     },
     items::{generate_assets_for_entries, pickup_item, EntitiesResource, ItemBundle},
-    ladder::LadderBundle,
-    map::{setup_map, spawn_wall_collision, update_level_selection, WallBundle},
+    ladder::{climbing_animation_state, spawn_ladder_tops, LadderBundle},
+    map::{
+        setup_map, spawn_one_way_platform_collision, spawn_wall_collision, spawn_water_collision,
+        update_level_selection, OneWayPlatformBundle, SlopeBundle, WallBundle, WaterBundle,
+    },
+    platform::{crumble_platforms, CrumblingPlatformBundle, MovingPlatformBundle},
     player::{
-        animate_sprite, movement, scale_player, setup_player, update_animation_state, PlayerBundle,
+        crouch, face_move_direction, movement, movement_animation_state, scale_player, PlayerBundle,
     },
     projectile::{
-        despawn_projectiles, projectiles_collisions, shoot_projectile, ProjectilesGlobalAmount,
+        despawn_projectiles, orient_arrows, projectiles_collisions, shoot_projectile, AimAssist,
+        ProjectilesGlobalAmount,
     },
-    ui::{setup_ui, update_health_ui},
+    rope::{spawn_rope_segments, swing, SwingableBundle},
+    status::{apply_status_effects, tick_status_effects, StatusEvent},
+    ui::{setup_ui, update_breath_ui, update_health_ui, update_resource_ui, update_status_ui},
+    water::{detect_submersion, update_breath},
+    weapon::{draw_bow, melee_hits, swing_sword},
 };
 
 struct Game;
@@ -27,6 +49,31 @@ struct PsysicsPlugin;
 struct MapPlugin;
 struct HelperPlugin;
 struct UiPlugin;
+struct ConfigPlugin;
+struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Input<Action>>()
+            .init_resource::<MovementAxes>()
+            .init_resource::<BufferedActions>()
+            .add_systems(Startup, (load_input_bindings, disable_gamepad_deadzone))
+            .add_systems(
+                PreUpdate,
+                (update_actions, buffer_actions).chain().after(InputSystem),
+            );
+    }
+}
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<MovementConfig>()
+            .init_asset_loader::<MovementConfigLoader>()
+            .init_resource::<MovementConfig>()
+            .add_systems(Startup, load_movement_config)
+            .add_systems(Update, apply_movement_config);
+    }
+}
 
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
@@ -37,6 +84,9 @@ impl Plugin for UiPlugin {
                 (
                     generate_assets_for_entries,
                     update_health_ui,
+                    update_resource_ui,
+                    update_breath_ui,
+                    update_status_ui,
                     update_inventory_ui,
                     update_selected_slot,
                     slot_buttons_system,
@@ -74,7 +124,7 @@ impl Plugin for PsysicsPlugin {
             RapierDebugRenderPlugin::default(),
         ))
         .insert_resource(RapierConfiguration {
-            gravity: Vec2::new(0.0, -1000.0),
+            gravity: Vec2::new(0.0, MovementConfig::default().gravity),
             ..Default::default()
         });
     }
@@ -82,36 +132,95 @@ impl Plugin for PsysicsPlugin {
 
 impl Plugin for Game {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, (setup_player,))
+        app.add_asset::<AsepriteSheet>()
+            .init_asset_loader::<AsepriteLoader>()
+            .add_event::<DamageEvent>()
+            .add_event::<StatusEvent>()
             .insert_resource(ProjectilesGlobalAmount::default())
+            .init_resource::<CursorWorldPosition>()
+            .init_resource::<AimAssist>()
             .insert_resource(Inventory::default())
             .add_systems(
                 Update,
                 (
                     scale_player,
-                    update_animation_state,
-                    detect_creature_death,
+                    (
+                        apply_aseprite_sheets,
+                        (
+                            movement_animation_state,
+                            climbing_animation_state,
+                            hurt_animation_state,
+                        ),
+                        select_animation,
+                        face_move_direction,
+                        animate,
+                    )
+                        .chain(),
+                    // every system sending damage events runs before they are resolved
+                    (
+                        (
+                            apply_status_effects
+                                .after(projectiles_collisions)
+                                .after(consume_selected_item),
+                            tick_status_effects,
+                        )
+                            .chain(),
+                        apply_damage
+                            .after(projectiles_collisions)
+                            .after(melee_hits)
+                            .after(detect_player_damage)
+                            .after(consume_selected_item)
+                            .after(update_breath),
+                        detect_creature_death,
+                    )
+                        .chain(),
                     camera_fit_inside_current_level,
-                    spawn_wall_collision,
-                    shoot_projectile,
-                    despawn_projectiles,
-                    projectiles_collisions,
+                    (
+                        spawn_wall_collision,
+                        spawn_one_way_platform_collision,
+                        spawn_water_collision,
+                        spawn_ladder_tops,
+                        spawn_rope_segments,
+                    ),
+                    detect_submersion,
+                    (update_breath, regenerate_mana),
+                    (
+                        (update_cursor_world_position, shoot_projectile, draw_bow).chain(),
+                        orient_arrows,
+                        despawn_projectiles,
+                        projectiles_collisions,
+                        (swing_sword, melee_hits).chain(),
+                    ),
                     detect_climb_range,
-                    patrol,
+                    (apply_knockback, patrol).chain(),
+                    crumble_platforms,
                     update_level_selection,
-                    detect_player_damage,
+                    (detect_player_damage, tick_invulnerability).chain(),
                     pickup_item,
                     consume_selected_item,
-                    animate_sprite,
                 ),
             )
-            .add_systems(FixedUpdate, (movement,))
+            .add_systems(
+                FixedUpdate,
+                (crouch, movement, swing, clear_buffered_actions).chain(),
+            )
             .register_ldtk_int_cell::<WallBundle>(1)
             .register_ldtk_entity::<EnemyBundle>("Mob")
             .register_ldtk_entity::<PlayerBundle>("Player")
+            .register_ldtk_entity::<MovingPlatformBundle>("MovingPlatform")
+            .register_ldtk_entity::<CrumblingPlatformBundle>("CrumblingPlatform")
+            .register_ldtk_entity::<SwingableBundle>("Swingable")
             .register_default_ldtk_entity_for_layer::<ItemBundle>("Items")
             .register_ldtk_int_cell::<LadderBundle>(2)
-            .register_ldtk_int_cell::<WallBundle>(3);
+            .register_ldtk_int_cell::<WallBundle>(3)
+            .register_ldtk_int_cell::<OneWayPlatformBundle>(4)
+            .register_ldtk_int_cell::<SlopeBundle>(5)
+            .register_ldtk_int_cell::<SlopeBundle>(6)
+            .register_ldtk_int_cell::<SlopeBundle>(7)
+            .register_ldtk_int_cell::<SlopeBundle>(8)
+            .register_ldtk_int_cell::<SlopeBundle>(9)
+            .register_ldtk_int_cell::<SlopeBundle>(10)
+            .register_ldtk_int_cell::<WaterBundle>(11);
     }
 }
 
@@ -131,6 +240,8 @@ fn main() {
             MapPlugin,
             HelperPlugin,
             UiPlugin,
+            ConfigPlugin,
+            ControlsPlugin,
         ))
         .add_systems(Update, close_on_esc)
         .add_systems(Startup, (setup_camera, setup_map, setup_ui))
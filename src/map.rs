@@ -1,11 +1,16 @@
 use bevy::{
+    ecs::query::ReadOnlyWorldQuery,
     prelude::*,
     utils::{HashMap, HashSet},
 };
 use bevy_ecs_ldtk::prelude::*;
 use bevy_rapier2d::prelude::*;
 
-use crate::{collisions::GameCollisions, particles::Paticle, player::Player};
+use crate::{
+    collisions::{ColliderBundle, GameCollisions},
+    particles::Paticle,
+    player::Player,
+};
 
 pub fn setup_map(mut commands: Commands, assets: Res<AssetServer>) {
     let map = assets.load("map.ldtk");
@@ -29,6 +34,223 @@ impl LdtkIntCell for WallBundle {
     }
 }
 
+pub(crate) const PLATFORM_THICKNESS: f32 = 4.;
+
+/// Solid only from above, the player can jump through it and drop down
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct OneWayPlatform;
+
+#[derive(Clone, Debug, Default, Bundle)]
+pub struct OneWayPlatformBundle {
+    platform: OneWayPlatform,
+}
+
+impl LdtkIntCell for OneWayPlatformBundle {
+    fn bundle_int_cell(_int_grid_cell: IntGridCell, _layer_instance: &LayerInstance) -> Self {
+        Self {
+            platform: OneWayPlatform,
+        }
+    }
+}
+
+/// Swimmable water, spawned as merged sensor volumes like the walls
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct Water;
+
+#[derive(Clone, Debug, Default, Bundle)]
+pub struct WaterBundle {
+    water: Water,
+}
+
+impl LdtkIntCell for WaterBundle {
+    fn bundle_int_cell(_int_grid_cell: IntGridCell, _layer_instance: &LayerInstance) -> Self {
+        Self { water: Water }
+    }
+}
+
+/// Ramp tile, named by the direction it rises towards
+///
+/// Shallow slopes take two tiles to climb one tile of height.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub enum Slope {
+    #[default]
+    SteepRight,
+    SteepLeft,
+    ShallowRightLow,
+    ShallowRightHigh,
+    ShallowLeftHigh,
+    ShallowLeftLow,
+}
+
+impl Slope {
+    pub fn from_int_grid_value(value: i32) -> Option<Self> {
+        match value {
+            5 => Some(Self::SteepRight),
+            6 => Some(Self::SteepLeft),
+            7 => Some(Self::ShallowRightLow),
+            8 => Some(Self::ShallowRightHigh),
+            9 => Some(Self::ShallowLeftHigh),
+            10 => Some(Self::ShallowLeftLow),
+            _ => None,
+        }
+    }
+
+    /// Height of the walkable surface at the left and right edge of the tile,
+    /// as a fraction of the tile size
+    pub fn surface_heights(&self) -> (f32, f32) {
+        match self {
+            Slope::SteepRight => (0., 1.),
+            Slope::SteepLeft => (1., 0.),
+            Slope::ShallowRightLow => (0., 0.5),
+            Slope::ShallowRightHigh => (0.5, 1.),
+            Slope::ShallowLeftHigh => (1., 0.5),
+            Slope::ShallowLeftLow => (0.5, 0.),
+        }
+    }
+
+    pub fn collider(&self, grid_size: f32) -> Collider {
+        let half = grid_size / 2.;
+        let (left, right) = self.surface_heights();
+        let points = [
+            Vec2::new(-half, -half),
+            Vec2::new(half, -half),
+            Vec2::new(half, -half + right * grid_size),
+            Vec2::new(-half, -half + left * grid_size),
+        ];
+        Collider::convex_hull(&points).expect("slope points to form a convex polygon")
+    }
+}
+
+#[derive(Clone, Debug, Default, Bundle)]
+pub struct SlopeBundle {
+    pub slope: Slope,
+    pub collider_bundle: ColliderBundle,
+}
+
+impl LdtkIntCell for SlopeBundle {
+    fn bundle_int_cell(int_grid_cell: IntGridCell, layer_instance: &LayerInstance) -> Self {
+        let slope = Slope::from_int_grid_value(int_grid_cell.value)
+            .expect("slope bundle to be registered for slope values only");
+        Self {
+            slope,
+            collider_bundle: ColliderBundle {
+                collider: slope.collider(layer_instance.grid_size as f32),
+                rigid_body: RigidBody::Fixed,
+                friction: Friction::new(1.0),
+                collision_groups: GameCollisions::Wall.into(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Represents a wide wall that is 1 tile tall
+/// Used to spawn wall collisions
+#[derive(Clone, Eq, PartialEq, Debug, Default, Hash)]
+struct Plate {
+    left: i32,
+    right: i32,
+}
+
+/// A simple rectangle type representing a wall of any size
+struct TileRect {
+    left: i32,
+    right: i32,
+    top: i32,
+    bottom: i32,
+}
+
+/// Groups int grid tiles by the level they belong to
+///
+/// The key of this map will be the entity of the level the tile belongs to.
+/// This has two consequences in the resulting collision entities:
+/// 1. it forces the colliders to be split along level boundaries
+/// 2. it lets us easily add the collision entities as children of the appropriate level entity
+pub(crate) fn tiles_by_level<'a, F: ReadOnlyWorldQuery>(
+    tiles: impl Iterator<Item = (&'a GridCoords, &'a Parent)>,
+    parent_query: &Query<&Parent, F>,
+) -> HashMap<Entity, HashSet<GridCoords>> {
+    let mut level_to_tile_locations: HashMap<Entity, HashSet<GridCoords>> = HashMap::new();
+
+    for (&grid_coords, parent) in tiles {
+        // An intgrid tile's direct parent will be a layer entity, not the level entity
+        // To get the level entity, you need the tile's grandparent.
+        // This is where parent_query comes in.
+        if let Ok(grandparent) = parent_query.get(parent.get()) {
+            level_to_tile_locations
+                .entry(grandparent.get())
+                .or_default()
+                .insert(grid_coords);
+        }
+    }
+
+    level_to_tile_locations
+}
+
+/// Combines tiles into flat "plates" in each individual row
+fn row_plates(tiles: &HashSet<GridCoords>, width: i32, height: i32) -> Vec<Vec<Plate>> {
+    let mut plate_stack: Vec<Vec<Plate>> = Vec::new();
+
+    for y in 0..height {
+        let mut row_plates: Vec<Plate> = Vec::new();
+        let mut plate_start = None;
+
+        // + 1 to the width so the algorithm "terminates" plates that touch the right edge
+        for x in 0..width + 1 {
+            match (plate_start, tiles.contains(&GridCoords { x, y })) {
+                (Some(s), false) => {
+                    row_plates.push(Plate {
+                        left: s,
+                        right: x - 1,
+                    });
+                    plate_start = None;
+                }
+                (None, true) => plate_start = Some(x),
+                _ => (),
+            }
+        }
+
+        plate_stack.push(row_plates);
+    }
+
+    plate_stack
+}
+
+/// Combines "plates" into rectangles across multiple rows
+fn merge_plates(mut plate_stack: Vec<Vec<Plate>>) -> Vec<TileRect> {
+    let mut rect_builder: HashMap<Plate, TileRect> = HashMap::new();
+    let mut prev_row: Vec<Plate> = Vec::new();
+    let mut rects: Vec<TileRect> = Vec::new();
+
+    // an extra empty row so the algorithm "finishes" the rects that touch the top edge
+    plate_stack.push(Vec::new());
+
+    for (y, current_row) in plate_stack.into_iter().enumerate() {
+        for prev_plate in &prev_row {
+            if !current_row.contains(prev_plate) {
+                // remove the finished rect so that the same plate in the future starts a new rect
+                if let Some(rect) = rect_builder.remove(prev_plate) {
+                    rects.push(rect);
+                }
+            }
+        }
+        for plate in &current_row {
+            rect_builder
+                .entry(plate.clone())
+                .and_modify(|e| e.top += 1)
+                .or_insert(TileRect {
+                    bottom: y as i32,
+                    top: y as i32,
+                    left: plate.left,
+                    right: plate.right,
+                });
+        }
+        prev_row = current_row;
+    }
+
+    rects
+}
+
 /// Spawns heron collisions for the walls of a level
 ///
 /// You could just insert a ColliderBundle in to the WallBundle,
@@ -52,42 +274,9 @@ pub fn spawn_wall_collision(
     level_query: Query<(Entity, &Handle<LdtkLevel>)>,
     levels: Res<Assets<LdtkLevel>>,
 ) {
-    /// Represents a wide wall that is 1 tile tall
-    /// Used to spawn wall collisions
-    #[derive(Clone, Eq, PartialEq, Debug, Default, Hash)]
-    struct Plate {
-        left: i32,
-        right: i32,
-    }
-
-    /// A simple rectangle type representing a wall of any size
-    struct Rect {
-        left: i32,
-        right: i32,
-        top: i32,
-        bottom: i32,
-    }
-
     // Consider where the walls are
     // storing them as GridCoords in a HashSet for quick, easy lookup
-    //
-    // The key of this map will be the entity of the level the wall belongs to.
-    // This has two consequences in the resulting collision entities:
-    // 1. it forces the walls to be split along level boundaries
-    // 2. it lets us easily add the collision entities as children of the appropriate level entity
-    let mut level_to_wall_locations: HashMap<Entity, HashSet<GridCoords>> = HashMap::new();
-
-    wall_query.for_each(|(&grid_coords, parent)| {
-        // An intgrid tile's direct parent will be a layer entity, not the level entity
-        // To get the level entity, you need the tile's grandparent.
-        // This is where parent_query comes in.
-        if let Ok(grandparent) = parent_query.get(parent.get()) {
-            level_to_wall_locations
-                .entry(grandparent.get())
-                .or_default()
-                .insert(grid_coords);
-        }
-    });
+    let level_to_wall_locations = tiles_by_level(wall_query.iter(), &parent_query);
 
     if !wall_query.is_empty() {
         level_query.for_each(|(level_entity, level_handle)| {
@@ -107,61 +296,7 @@ pub fn spawn_wall_collision(
                     .clone()
                     .expect("Level asset should have layers")[0];
 
-                // combine wall tiles into flat "plates" in each individual row
-                let mut plate_stack: Vec<Vec<Plate>> = Vec::new();
-
-                for y in 0..height {
-                    let mut row_plates: Vec<Plate> = Vec::new();
-                    let mut plate_start = None;
-
-                    // + 1 to the width so the algorithm "terminates" plates that touch the right edge
-                    for x in 0..width + 1 {
-                        match (plate_start, level_walls.contains(&GridCoords { x, y })) {
-                            (Some(s), false) => {
-                                row_plates.push(Plate {
-                                    left: s,
-                                    right: x - 1,
-                                });
-                                plate_start = None;
-                            }
-                            (None, true) => plate_start = Some(x),
-                            _ => (),
-                        }
-                    }
-
-                    plate_stack.push(row_plates);
-                }
-
-                // combine "plates" into rectangles across multiple rows
-                let mut rect_builder: HashMap<Plate, Rect> = HashMap::new();
-                let mut prev_row: Vec<Plate> = Vec::new();
-                let mut wall_rects: Vec<Rect> = Vec::new();
-
-                // an extra empty row so the algorithm "finishes" the rects that touch the top edge
-                plate_stack.push(Vec::new());
-
-                for (y, current_row) in plate_stack.into_iter().enumerate() {
-                    for prev_plate in &prev_row {
-                        if !current_row.contains(prev_plate) {
-                            // remove the finished rect so that the same plate in the future starts a new rect
-                            if let Some(rect) = rect_builder.remove(prev_plate) {
-                                wall_rects.push(rect);
-                            }
-                        }
-                    }
-                    for plate in &current_row {
-                        rect_builder
-                            .entry(plate.clone())
-                            .and_modify(|e| e.top += 1)
-                            .or_insert(Rect {
-                                bottom: y as i32,
-                                top: y as i32,
-                                left: plate.left,
-                                right: plate.right,
-                            });
-                    }
-                    prev_row = current_row;
-                }
+                let wall_rects = merge_plates(row_plates(level_walls, width, height));
 
                 commands.entity(level_entity).with_children(|level| {
                     // Spawn colliders for every rectangle..
@@ -179,6 +314,7 @@ pub fn spawn_wall_collision(
                                     * grid_size as f32
                                     / 2.,
                             ))
+                            .insert(Wall)
                             .insert(CollisionGroups::from(GameCollisions::Wall.into()))
                             .insert(RigidBody::Fixed)
                             .insert(Friction::new(1.0))
@@ -197,6 +333,125 @@ pub fn spawn_wall_collision(
     }
 }
 
+/// Spawns thin colliders along the top edge of one-way platform tiles
+///
+/// Platforms are only merged within a row, stacking them would create
+/// a thick block the player can't fall through.
+pub fn spawn_one_way_platform_collision(
+    mut commands: Commands,
+    platform_query: Query<(&GridCoords, &Parent), Added<OneWayPlatform>>,
+    parent_query: Query<&Parent, Without<OneWayPlatform>>,
+    level_query: Query<(Entity, &Handle<LdtkLevel>)>,
+    levels: Res<Assets<LdtkLevel>>,
+) {
+    if platform_query.is_empty() {
+        return;
+    }
+    let level_to_platform_locations = tiles_by_level(platform_query.iter(), &parent_query);
+
+    level_query.for_each(|(level_entity, level_handle)| {
+        if let Some(level_platforms) = level_to_platform_locations.get(&level_entity) {
+            let level = levels
+                .get(level_handle)
+                .expect("Level should be loaded by this point");
+
+            let LayerInstance {
+                c_wid: width,
+                c_hei: height,
+                grid_size,
+                ..
+            } = level
+                .level
+                .layer_instances
+                .clone()
+                .expect("Level asset should have layers")[0];
+            let grid_size = grid_size as f32;
+            let collision_groups: CollisionGroups = GameCollisions::Platform.into();
+
+            commands.entity(level_entity).with_children(|level| {
+                for (y, row) in row_plates(level_platforms, width, height)
+                    .into_iter()
+                    .enumerate()
+                {
+                    for plate in row {
+                        level.spawn((
+                            Collider::cuboid(
+                                (plate.right - plate.left + 1) as f32 * grid_size / 2.,
+                                PLATFORM_THICKNESS / 2.,
+                            ),
+                            OneWayPlatform,
+                            collision_groups,
+                            RigidBody::Fixed,
+                            Friction::new(1.0),
+                            TransformBundle::from_transform(Transform::from_xyz(
+                                (plate.left + plate.right + 1) as f32 * grid_size / 2.,
+                                (y + 1) as f32 * grid_size - PLATFORM_THICKNESS / 2.,
+                                0.,
+                            )),
+                        ));
+                    }
+                }
+            });
+        }
+    });
+}
+
+/// Spawns sensor volumes covering the water tiles of a level
+///
+/// Uses the same rectangle merging as the walls, so a pool is usually a single collider.
+pub fn spawn_water_collision(
+    mut commands: Commands,
+    water_query: Query<(&GridCoords, &Parent), Added<Water>>,
+    parent_query: Query<&Parent, Without<Water>>,
+    level_query: Query<(Entity, &Handle<LdtkLevel>)>,
+    levels: Res<Assets<LdtkLevel>>,
+) {
+    if water_query.is_empty() {
+        return;
+    }
+    let level_to_water_locations = tiles_by_level(water_query.iter(), &parent_query);
+
+    level_query.for_each(|(level_entity, level_handle)| {
+        if let Some(level_water) = level_to_water_locations.get(&level_entity) {
+            let level = levels
+                .get(level_handle)
+                .expect("Level should be loaded by this point");
+
+            let LayerInstance {
+                c_wid: width,
+                c_hei: height,
+                grid_size,
+                ..
+            } = level
+                .level
+                .layer_instances
+                .clone()
+                .expect("Level asset should have layers")[0];
+            let grid_size = grid_size as f32;
+            let collision_groups: CollisionGroups = GameCollisions::Water.into();
+
+            commands.entity(level_entity).with_children(|level| {
+                for rect in merge_plates(row_plates(level_water, width, height)) {
+                    level.spawn((
+                        Collider::cuboid(
+                            (rect.right - rect.left + 1) as f32 * grid_size / 2.,
+                            (rect.top - rect.bottom + 1) as f32 * grid_size / 2.,
+                        ),
+                        Sensor,
+                        Water,
+                        collision_groups,
+                        TransformBundle::from_transform(Transform::from_xyz(
+                            (rect.left + rect.right + 1) as f32 * grid_size / 2.,
+                            (rect.bottom + rect.top + 1) as f32 * grid_size / 2.,
+                            0.,
+                        )),
+                    ));
+                }
+            });
+        }
+    });
+}
+
 pub fn update_level_selection(
     mut commands: Commands,
     level_query: Query<(&Transform, &Handle<LdtkLevel>), Without<Player>>,
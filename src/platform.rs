@@ -0,0 +1,210 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{prelude::*, utils::ldtk_pixel_coords_to_translation_pivoted};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    collisions::{ColliderBundle, GameCollisions},
+    enemy::Patrol,
+    player::{standing_on, Player},
+};
+
+#[derive(Component, Default, Debug)]
+pub struct MovingPlatform;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CrumbleState {
+    #[default]
+    Solid,
+    Crumbling,
+    Collapsed,
+}
+
+#[derive(Component, Default, Debug)]
+pub struct CrumblingPlatform {
+    pub state: CrumbleState,
+    /// Time between the first step on the platform and its collapse
+    pub crumble_timer: Timer,
+    pub respawn_timer: Timer,
+}
+
+#[derive(Bundle, Default)]
+pub struct MovingPlatformBundle {
+    pub moving_platform: MovingPlatform,
+    pub patrol: Patrol,
+    pub collider_bundle: ColliderBundle,
+    pub sprite: SpriteSheetBundle,
+}
+
+#[derive(Bundle, Default)]
+pub struct CrumblingPlatformBundle {
+    pub crumbling_platform: CrumblingPlatform,
+    pub collider_bundle: ColliderBundle,
+    pub sprite: SpriteSheetBundle,
+}
+
+fn platform_sprite(
+    entity_instance: &EntityInstance,
+    tileset: Option<&Handle<Image>>,
+    tileset_definition: Option<&TilesetDefinition>,
+    texture_atlases: &mut Assets<TextureAtlas>,
+) -> SpriteSheetBundle {
+    let (Some(tileset), Some(tileset_definition), Some(tile)) =
+        (tileset, tileset_definition, entity_instance.tile)
+    else {
+        return SpriteSheetBundle::default();
+    };
+    let texture_atlas = TextureAtlas::from_grid(
+        tileset.clone(),
+        Vec2::new(tile.w as f32, tile.h as f32),
+        1,
+        1,
+        Some(Vec2::splat(tileset_definition.padding as f32)),
+        Some(Vec2::new(tile.x as f32, tile.y as f32)),
+    );
+    SpriteSheetBundle {
+        sprite: TextureAtlasSprite {
+            custom_size: Some(Vec2::new(
+                entity_instance.width as f32,
+                entity_instance.height as f32,
+            )),
+            ..Default::default()
+        },
+        texture_atlas: texture_atlases.add(texture_atlas),
+        ..Default::default()
+    }
+}
+
+fn platform_collider(entity_instance: &EntityInstance, rigid_body: RigidBody) -> ColliderBundle {
+    ColliderBundle {
+        collider: Collider::cuboid(
+            entity_instance.width as f32 / 2.,
+            entity_instance.height as f32 / 2.,
+        ),
+        rigid_body,
+        rotation_constraints: LockedAxes::ROTATION_LOCKED,
+        friction: Friction::new(1.0),
+        collision_groups: GameCollisions::Wall.into(),
+        ..Default::default()
+    }
+}
+
+/// Path from the optional `patrol` field, platforms without one stay in place
+fn platform_patrol(entity_instance: &EntityInstance, layer_instance: &LayerInstance) -> Patrol {
+    let grid_size = layer_instance.grid_size as f32;
+    let to_translation = |pixel_coords: IVec2| {
+        ldtk_pixel_coords_to_translation_pivoted(
+            pixel_coords,
+            layer_instance.c_hei * layer_instance.grid_size,
+            IVec2::new(entity_instance.width, entity_instance.height),
+            entity_instance.pivot,
+        )
+    };
+
+    let mut points = vec![to_translation(entity_instance.px)];
+    if let Ok(path) = entity_instance.iter_points_field("patrol") {
+        // a point marks a cell, the platform pivot sits at the same spot inside it
+        points.extend(path.map(|point| {
+            to_translation(((point.as_vec2() + entity_instance.pivot) * grid_size).as_ivec2())
+        }));
+    }
+
+    Patrol {
+        points,
+        index: 1,
+        forward: true,
+    }
+}
+
+impl LdtkEntity for MovingPlatformBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        layer_instance: &LayerInstance,
+        tileset: Option<&Handle<Image>>,
+        tileset_definition: Option<&TilesetDefinition>,
+        _asset_server: &AssetServer,
+        texture_atlases: &mut Assets<TextureAtlas>,
+    ) -> Self {
+        Self {
+            moving_platform: MovingPlatform,
+            patrol: platform_patrol(entity_instance, layer_instance),
+            collider_bundle: platform_collider(entity_instance, RigidBody::KinematicVelocityBased),
+            sprite: platform_sprite(
+                entity_instance,
+                tileset,
+                tileset_definition,
+                texture_atlases,
+            ),
+        }
+    }
+}
+
+impl LdtkEntity for CrumblingPlatformBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        _layer_instance: &LayerInstance,
+        tileset: Option<&Handle<Image>>,
+        tileset_definition: Option<&TilesetDefinition>,
+        _asset_server: &AssetServer,
+        texture_atlases: &mut Assets<TextureAtlas>,
+    ) -> Self {
+        let crumble_delay = entity_instance
+            .get_float_field("crumble_delay")
+            .copied()
+            .unwrap_or(0.5);
+        let respawn_delay = entity_instance
+            .get_float_field("respawn_delay")
+            .copied()
+            .unwrap_or(3.);
+
+        Self {
+            crumbling_platform: CrumblingPlatform {
+                state: CrumbleState::Solid,
+                crumble_timer: Timer::from_seconds(crumble_delay, TimerMode::Once),
+                respawn_timer: Timer::from_seconds(respawn_delay, TimerMode::Once),
+            },
+            collider_bundle: platform_collider(entity_instance, RigidBody::Fixed),
+            sprite: platform_sprite(
+                entity_instance,
+                tileset,
+                tileset_definition,
+                texture_atlases,
+            ),
+        }
+    }
+}
+
+pub fn crumble_platforms(
+    mut commands: Commands,
+    player: Query<&KinematicCharacterControllerOutput, With<Player>>,
+    mut platforms: Query<(Entity, &mut CrumblingPlatform, &mut Visibility)>,
+    time: Res<Time>,
+) {
+    for (entity, mut platform, mut visibility) in &mut platforms {
+        match platform.state {
+            CrumbleState::Solid => {
+                let stepped_on = player
+                    .iter()
+                    .any(|output| standing_on(output).any(|ground| ground == entity));
+                if stepped_on {
+                    platform.crumble_timer.reset();
+                    platform.state = CrumbleState::Crumbling;
+                }
+            }
+            CrumbleState::Crumbling => {
+                if platform.crumble_timer.tick(time.delta()).finished() {
+                    commands.entity(entity).insert(ColliderDisabled);
+                    *visibility = Visibility::Hidden;
+                    platform.respawn_timer.reset();
+                    platform.state = CrumbleState::Collapsed;
+                }
+            }
+            CrumbleState::Collapsed => {
+                if platform.respawn_timer.tick(time.delta()).finished() {
+                    commands.entity(entity).remove::<ColliderDisabled>();
+                    *visibility = Visibility::Inherited;
+                    platform.state = CrumbleState::Solid;
+                }
+            }
+        }
+    }
+}
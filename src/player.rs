@@ -3,23 +3,30 @@ use bevy_ecs_ldtk::prelude::LdtkEntity;
 use bevy_rapier2d::prelude::*;
 
 use crate::{
-    collisions::{ColliderBundle, GameCollisions},
-    creature::{CreatureBundle, Damage, Health},
-    ladder::Climber,
+    animation::{
+        AnimationBundle, AnimationCondition, AnimationRule, AnimationRules, AnimationState,
+    },
+    aseprite::AsepriteAnimation,
+    collisions::{collider_bounds, ColliderBundle, GameCollisions},
+    config::MovementConfig,
+    controls::{Action, BufferedActions, MovementAxes},
+    creature::{
+        CreatureBundle, Damage, DamageKind, Health, Invulnerability, Mana, MaxHealth, HURT,
+    },
+    ladder::{Climbable, Climber, LadderTop, CLIMBING},
+    map::{OneWayPlatform, Wall},
+    platform::MovingPlatform,
+    rope::Swinger,
+    status::StatusEffects,
+    water::{Breath, Submerged},
 };
 
-#[derive(Component, Default, Debug)]
-pub struct AnimationIndices {
-    pub first: usize,
-    pub last: usize,
-}
-
-#[derive(Component, Default, Debug)]
-pub struct AnimationTimer(pub Timer);
-
 #[derive(Component, Default, Debug)]
 pub struct Player;
 
+const STANDING_HALF_EXTENTS: Vec2 = Vec2::new(4., 8.);
+const CROUCHING_HALF_EXTENTS: Vec2 = Vec2::new(4., 4.);
+
 #[derive(Component, Default, Debug)]
 pub enum MoveDirection {
     Right,
@@ -28,45 +35,191 @@ pub enum MoveDirection {
     Idle,
 }
 
-#[derive(Bundle, Default)]
-pub struct AnimationBundle {
-    pub animation_indices: AnimationIndices,
-    pub animation_timer: AnimationTimer,
+/// Vertical speed of the player in pixels per second
+#[derive(Component, Default, Debug)]
+pub struct VerticalVelocity(pub f32);
+
+/// Grace windows for jumping, measured in `FixedUpdate` ticks
+#[derive(Component, Debug)]
+pub struct JumpGrace {
+    pub ticks_since_grounded: u32,
+    pub ticks_since_jump_pressed: u32,
+    /// Rising from a jump the player started, only such jumps are cut short
+    pub jumping: bool,
 }
 
-#[derive(Resource)]
-pub struct PlayerAtlases {
-    pub idle: Handle<TextureAtlas>,
-    pub run: Handle<TextureAtlas>,
-    pub fall: Handle<TextureAtlas>,
+impl Default for JumpGrace {
+    fn default() -> Self {
+        Self {
+            ticks_since_grounded: u32::MAX,
+            ticks_since_jump_pressed: u32::MAX,
+            jumping: false,
+        }
+    }
 }
 
-pub fn animate_sprite(
-    mut query: Query<
-        (
-            &mut AnimationTimer,
-            &AnimationIndices,
-            &mut TextureAtlasSprite,
-        ),
-        With<Player>,
-    >,
-    time: Res<Time>,
-) {
-    for (mut timer, indicies, mut sprite) in &mut query {
-        let AnimationTimer(timer) = &mut *timer;
-        timer.tick(time.delta());
-        if timer.just_finished() {
-            if sprite.index >= indicies.last {
-                sprite.index = 0;
-                continue;
+impl JumpGrace {
+    pub fn tick(&mut self, grounded: bool, jump_pressed: bool) {
+        self.ticks_since_grounded = if grounded {
+            0
+        } else {
+            self.ticks_since_grounded.saturating_add(1)
+        };
+        self.ticks_since_jump_pressed = if jump_pressed {
+            0
+        } else {
+            self.ticks_since_jump_pressed.saturating_add(1)
+        };
+    }
+
+    /// Consumes a buffered jump press regardless of the ground state
+    pub fn take_jump_press(&mut self, config: &MovementConfig) -> bool {
+        let pressed = self.ticks_since_jump_pressed <= config.jump_buffer_ticks;
+        if pressed {
+            self.ticks_since_jump_pressed = u32::MAX;
+        }
+        pressed
+    }
+
+    /// Consumes both windows if a jump should happen this tick
+    pub fn try_jump(&mut self, config: &MovementConfig) -> bool {
+        let can_jump = self.ticks_since_grounded <= config.coyote_ticks
+            && self.ticks_since_jump_pressed <= config.jump_buffer_ticks;
+        if can_jump {
+            self.ticks_since_grounded = u32::MAX;
+            self.ticks_since_jump_pressed = u32::MAX;
+        }
+        can_jump
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WallSide {
+    Left,
+    Right,
+}
+
+impl WallSide {
+    /// Takes the contact normal on the character's shape
+    pub fn from_normal(normal: Vec2) -> Option<Self> {
+        if normal.x > 0.9 {
+            Some(Self::Right)
+        } else if normal.x < -0.9 {
+            Some(Self::Left)
+        } else {
+            None
+        }
+    }
+}
+
+/// Wall the player is pressed against while airborne
+#[derive(Component, Debug)]
+pub struct WallContact {
+    pub side: Option<WallSide>,
+    pub ticks_since_contact: u32,
+}
+
+impl Default for WallContact {
+    fn default() -> Self {
+        Self {
+            side: None,
+            ticks_since_contact: u32::MAX,
+        }
+    }
+}
+
+impl WallContact {
+    pub fn update(&mut self, touched: Option<WallSide>, grounded: bool, grace_ticks: u32) {
+        if grounded {
+            self.side = None;
+            self.ticks_since_contact = u32::MAX;
+        } else if touched.is_some() {
+            self.side = touched;
+            self.ticks_since_contact = 0;
+        } else {
+            self.ticks_since_contact = self.ticks_since_contact.saturating_add(1);
+            if self.ticks_since_contact > grace_ticks {
+                self.side = None;
             }
-            sprite.index = if sprite.index == indicies.last {
-                indicies.first
-            } else {
-                sprite.index + 1
+        }
+    }
+}
+
+/// Horizontal motion that overrides player input for a few ticks
+#[derive(Component, Default, Debug)]
+pub struct HorizontalImpulse {
+    /// Pixels per second
+    pub speed: f32,
+    pub ticks: u32,
+}
+
+/// Push requested outside the fixed step, by hits for instance
+///
+/// `movement` takes it on its next tick, it is never cut short like a jump.
+#[derive(Component, Default, Debug)]
+pub struct PendingLaunch {
+    pub vertical_speed: Option<f32>,
+    pub impulse: Option<HorizontalImpulse>,
+}
+
+/// Dash state, every counter is measured in `FixedUpdate` ticks
+#[derive(Component, Default, Debug)]
+pub struct Dash {
+    pub active_ticks: u32,
+    pub cooldown_ticks: u32,
+    pub invulnerable_ticks: u32,
+    /// Dashes performed since the last time the player was grounded
+    pub air_dashes: u32,
+}
+
+impl Dash {
+    pub fn tick(&mut self, grounded: bool) {
+        self.active_ticks = self.active_ticks.saturating_sub(1);
+        self.cooldown_ticks = self.cooldown_ticks.saturating_sub(1);
+        self.invulnerable_ticks = self.invulnerable_ticks.saturating_sub(1);
+        if grounded {
+            self.air_dashes = 0;
+        }
+    }
+
+    pub fn try_start(&mut self, grounded: bool, config: &MovementConfig) -> bool {
+        if self.cooldown_ticks > 0 {
+            return false;
+        }
+        if !grounded {
+            if config
+                .max_air_dashes
+                .is_some_and(|max| self.air_dashes >= max)
+            {
+                return false;
             }
-        };
+            self.air_dashes += 1;
+        }
+        self.active_ticks = config.dash_ticks;
+        self.cooldown_ticks = config.dash_cooldown_ticks;
+        self.invulnerable_ticks = config.dash_invulnerable_ticks;
+        true
     }
+
+    pub fn is_dashing(&self) -> bool {
+        self.active_ticks > 0
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerable_ticks > 0
+    }
+}
+
+/// Ticks left during which one-way platforms are ignored
+#[derive(Component, Default, Debug)]
+pub struct PlatformDrop {
+    pub ticks: u32,
+}
+
+/// While crouching the player uses a shorter collider
+#[derive(Component, Default, Debug)]
+pub struct Crouch {
+    pub crouching: bool,
 }
 
 pub fn scale_player(mut q: Query<&mut Transform, Added<Player>>) {
@@ -75,139 +228,393 @@ pub fn scale_player(mut q: Query<&mut Transform, Added<Player>>) {
     }
 }
 
-pub fn setup_player(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+/// Transitions between the frame tags of `atlas/player.aseprite.json`
+fn player_animation() -> AnimationBundle {
+    let rules = AnimationRules(vec![
+        AnimationRule::new(AnimationCondition::State(HURT), "hurt"),
+        AnimationRule::new(AnimationCondition::State(CLIMBING), "climb"),
+        AnimationRule::new(AnimationCondition::State(CROUCHING), "crouch"),
+        AnimationRule::new(
+            AnimationCondition::Not(Box::new(AnimationCondition::State(GROUNDED))),
+            "fall",
+        ),
+        AnimationRule::new(AnimationCondition::State(MOVING), "run"),
+        AnimationRule::new(AnimationCondition::Always, "idle"),
+    ]);
+    AnimationBundle {
+        rules,
+        ..Default::default()
+    }
+}
+
+/// Animation state labels of moving creatures
+pub const MOVING: &str = "moving";
+pub const GROUNDED: &str = "grounded";
+pub const CROUCHING: &str = "crouching";
+
+pub fn movement_animation_state(
+    mut query: Query<(
+        &mut AnimationState,
+        Option<&MoveDirection>,
+        Option<&KinematicCharacterControllerOutput>,
+        Option<&Crouch>,
+    )>,
 ) {
-    let run_texture_handle = asset_server.load("atlas/player_run.png");
-    let idle_texture_handle = asset_server.load("atlas/player_idle.png");
-    let fall_texture_handle = asset_server.load("atlas/player_fall.png");
-
-    let run_texture_atlas =
-        TextureAtlas::from_grid(run_texture_handle, [16.0, 16.0].into(), 1, 4, None, None);
-    let idle_texture_atlas =
-        TextureAtlas::from_grid(idle_texture_handle, [16.0, 16.0].into(), 1, 4, None, None);
-    let fall_texture_atlas =
-        TextureAtlas::from_grid(fall_texture_handle, [16.0, 16.0].into(), 1, 2, None, None);
-
-    let run_texture_atlas_handle = texture_atlases.add(run_texture_atlas);
-    let idle_texture_atlas_handle = texture_atlases.add(idle_texture_atlas);
-    let fall_texture_atlas_handle = texture_atlases.add(fall_texture_atlas);
-
-    commands.insert_resource(PlayerAtlases {
-        idle: idle_texture_atlas_handle,
-        run: run_texture_atlas_handle,
-        fall: fall_texture_atlas_handle,
-    });
+    for (mut state, direction, output, crouch) in &mut query {
+        state.set(
+            MOVING,
+            direction.is_some_and(|direction| !matches!(direction, MoveDirection::Idle)),
+        );
+        state.set(
+            GROUNDED,
+            output.map(|output| output.grounded).unwrap_or(true),
+        );
+        state.set(CROUCHING, crouch.is_some_and(|crouch| crouch.crouching));
+    }
+}
+
+pub fn face_move_direction(mut query: Query<(&MoveDirection, &mut TextureAtlasSprite)>) {
+    for (direction, mut sprite) in &mut query {
+        match direction {
+            MoveDirection::Right => sprite.flip_x = false,
+            MoveDirection::Left => sprite.flip_x = true,
+            MoveDirection::Idle => (),
+        }
+    }
+}
+
+/// Entities the character landed on during its last move
+pub fn standing_on(
+    output: &KinematicCharacterControllerOutput,
+) -> impl Iterator<Item = Entity> + '_ {
+    output
+        .collisions
+        .iter()
+        .filter(|collision| collision.toi.normal1.y < -0.7)
+        .map(|collision| collision.entity)
 }
 
-pub fn update_animation_state(
-    assets: Res<PlayerAtlases>,
+/// Shorter box sitting at the bottom of the standing one, so the feet and the sprite stay in place
+fn crouching_collider() -> Collider {
+    Collider::compound(vec![(
+        Vec2::new(0., CROUCHING_HALF_EXTENTS.y - STANDING_HALF_EXTENTS.y),
+        0.,
+        Collider::cuboid(CROUCHING_HALF_EXTENTS.x, CROUCHING_HALF_EXTENTS.y),
+    )])
+}
+
+/// Swaps between the standing and crouching collider, runs before `movement`
+pub fn crouch(
+    axes: Res<MovementAxes>,
     mut query: Query<
         (
-            &mut TextureAtlasSprite,
-            &MoveDirection,
-            &mut AnimationIndices,
+            &mut Crouch,
+            &mut Collider,
+            &Transform,
             &KinematicCharacterControllerOutput,
-            &mut Handle<TextureAtlas>,
+            &Climber,
+            Option<&Submerged>,
         ),
         With<Player>,
     >,
+    ladder_tops: Query<(), With<LadderTop>>,
+    rapier_context: Res<RapierContext>,
 ) {
-    for (mut sprite, direction, mut indicies, output, mut texture) in &mut query {
-        match *direction {
-            MoveDirection::Right => {
-                sprite.flip_x = false;
-            }
-            MoveDirection::Left => {
-                sprite.flip_x = true;
-            }
-            _ => (),
-        };
-        if !output.grounded {
-            indicies.last = 1;
-            if sprite.index > 1 {
-                sprite.index = 0;
-            }
-            *texture = assets.fall.clone();
+    for (mut crouch, mut collider, transform, output, climber, submerged) in &mut query {
+        // pressing down on top of a ladder climbs down instead
+        let on_ladder_top = standing_on(output).any(|ground| ladder_tops.contains(ground));
+        let wants_crouch = axes.vertical < 0.
+            && output.grounded
+            && !climber.climbing
+            && !on_ladder_top
+            && submerged.is_none();
+        if wants_crouch == crouch.crouching {
+            continue;
+        }
+
+        let scale = transform.scale.truncate();
+        if wants_crouch {
+            *collider = crouching_collider();
         } else {
-            match *direction {
-                MoveDirection::Right | MoveDirection::Left => {
-                    indicies.last = 3;
-                    if sprite.index > 3 {
-                        sprite.index = 0;
-                    }
-                    *texture = assets.run.clone();
-                }
-                MoveDirection::Idle => {
-                    indicies.last = 3;
-                    if sprite.index > 3 {
-                        sprite.index = 0;
-                    }
-                    *texture = assets.idle.clone();
-                }
-            };
+            // only check the space the head would grow into, so the floor doesn't count
+            let growth = (STANDING_HALF_EXTENTS.y - CROUCHING_HALF_EXTENTS.y) * scale.y;
+            let blocked = rapier_context
+                .intersection_with_shape(
+                    Vec2::new(
+                        transform.translation.x,
+                        transform.translation.y + CROUCHING_HALF_EXTENTS.y * scale.y,
+                    ),
+                    0.,
+                    &Collider::cuboid(STANDING_HALF_EXTENTS.x * scale.x - 0.5, growth),
+                    QueryFilter::new()
+                        .exclude_sensors()
+                        .groups(CollisionGroups::new(
+                            Group::ALL,
+                            GameCollisions::Wall.into(),
+                        )),
+                )
+                .is_some();
+            if blocked {
+                continue;
+            }
+            *collider = Collider::cuboid(STANDING_HALF_EXTENTS.x, STANDING_HALF_EXTENTS.y);
         }
+        crouch.crouching = wants_crouch;
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn movement(
-    input: Res<Input<KeyCode>>,
+    actions: Res<Input<Action>>,
+    buffered: Res<BufferedActions>,
+    axes: Res<MovementAxes>,
     mut query: Query<
         (
             &mut KinematicCharacterController,
             &KinematicCharacterControllerOutput,
             &mut Climber,
             &mut MoveDirection,
+            &mut JumpGrace,
+            &mut VerticalVelocity,
+            &mut WallContact,
+            &mut HorizontalImpulse,
+            &mut Dash,
+            &TextureAtlasSprite,
+            &mut PlatformDrop,
+            &GlobalTransform,
+            &Collider,
+            Option<&Submerged>,
+            (&Crouch, &StatusEffects, &mut PendingLaunch),
         ),
         With<Player>,
     >,
+    walls: Query<(), With<Wall>>,
+    platforms: Query<(), With<OneWayPlatform>>,
+    ladder_tops: Query<(), With<LadderTop>>,
+    ladders: Query<&GlobalTransform, Or<(With<Climbable>, With<LadderTop>)>>,
+    moving_platforms: Query<&Velocity, With<MovingPlatform>>,
+    rapier_context: Res<RapierContext>,
+    config: Res<MovementConfig>,
     time: Res<FixedTime>,
 ) {
-    for (mut controller, output, mut climber, mut direction) in &mut query {
-        let right = (input.pressed(KeyCode::E) || input.pressed(KeyCode::Right))
-            .then_some(1.)
-            .unwrap_or(0.);
-        let left = (input.pressed(KeyCode::A) || input.pressed(KeyCode::Left))
-            .then_some(1.)
-            .unwrap_or(0.);
-        let mut transition_vector = output.effective_translation;
-
-        transition_vector.x = (right - left) * 0.2 * time.period.as_millis() as f32;
-
-        if left == 1. {
+    let delta = time.period.as_secs_f32();
+    for (
+        mut controller,
+        output,
+        mut climber,
+        mut direction,
+        mut jump_grace,
+        mut velocity,
+        mut wall_contact,
+        mut impulse,
+        mut dash,
+        sprite,
+        mut platform_drop,
+        transform,
+        collider,
+        submerged,
+        (crouch, status_effects, mut launch),
+    ) in &mut query
+    {
+        let jump_held = actions.pressed(Action::Jump);
+        let mut transition_vector = Vec2::ZERO;
+
+        dash.tick(output.grounded);
+        if buffered.just_pressed(Action::Dash) && dash.try_start(output.grounded, &config) {
+            let facing = match *direction {
+                MoveDirection::Right => 1.,
+                MoveDirection::Left => -1.,
+                MoveDirection::Idle if sprite.flip_x => -1.,
+                MoveDirection::Idle => 1.,
+            };
+            *impulse = HorizontalImpulse {
+                speed: facing * config.dash_speed,
+                ticks: config.dash_ticks,
+            };
+        }
+
+        if let Some(launch_impulse) = launch.impulse.take() {
+            *impulse = launch_impulse;
+        }
+        if impulse.ticks > 0 {
+            impulse.ticks -= 1;
+            transition_vector.x = impulse.speed * delta;
+        } else {
+            let run_speed = if crouch.crouching {
+                config.crouch_speed
+            } else if submerged.is_some() {
+                config.water_run_speed
+            } else {
+                config.run_speed
+            };
+            transition_vector.x =
+                axes.horizontal * run_speed * status_effects.speed_multiplier() * delta;
+        }
+
+        // facing follows the input, so knockback and dashes don't turn the player around
+        if axes.horizontal < 0. {
             *direction = MoveDirection::Left;
-        } else if right == 1. {
+        } else if axes.horizontal > 0. {
             *direction = MoveDirection::Right;
         } else {
             *direction = MoveDirection::Idle;
         }
 
-        if climber.intersecting_climbables.is_empty() {
+        let (scale, _, position) = transform.to_scale_rotation_translation();
+        let (center, half_extents) = collider_bounds(collider);
+        let half_extents = half_extents * scale.truncate();
+        let overlapped_platform = rapier_context.intersection_with_shape(
+            position.truncate() + center * scale.truncate(),
+            0.,
+            &Collider::cuboid(half_extents.x, half_extents.y),
+            QueryFilter::new().groups(CollisionGroups::new(
+                Group::ALL,
+                GameCollisions::Platform.into(),
+            )),
+        );
+        let inside_platform = overlapped_platform.is_some();
+
+        // the ladder top is not a climbable, standing on it or passing through it still counts
+        let on_ladder_top =
+            output.grounded && standing_on(output).any(|ground| ladder_tops.contains(ground));
+        let ladder = climber
+            .intersecting_climbables
+            .iter()
+            .copied()
+            .chain(standing_on(output).filter(|ground| ladder_tops.contains(*ground)))
+            .chain(overlapped_platform.filter(|platform| ladder_tops.contains(*platform)))
+            .next();
+        if ladder.is_none() {
             climber.climbing = false;
-        } else if input.just_pressed(KeyCode::Comma) || input.just_pressed(KeyCode::O) {
+        } else if buffered.just_pressed(Action::ClimbDown)
+            || (buffered.just_pressed(Action::ClimbUp)
+                && !climber.intersecting_climbables.is_empty())
+        {
             climber.climbing = true;
         }
+        // reached the floor at the bottom of the ladder
+        if climber.climbing && output.grounded && !on_ladder_top && axes.vertical < 0. {
+            climber.climbing = false;
+        }
 
-        if climber.climbing {
-            let up = input.pressed(KeyCode::Comma).then_some(1.).unwrap_or(0.);
-            let down = input.pressed(KeyCode::O).then_some(1.).unwrap_or(0.);
+        // landed or bumped the head on the previous tick
+        let hit_ceiling = output.desired_translation.y > 0.
+            && output.effective_translation.y < output.desired_translation.y - 0.01;
+        if (output.grounded && velocity.0 < 0.) || (hit_ceiling && velocity.0 > 0.) {
+            velocity.0 = 0.;
+        }
 
-            transition_vector.y = (up - down) * 2.;
+        let touched_wall = output
+            .collisions
+            .iter()
+            .filter(|collision| walls.contains(collision.entity))
+            .find_map(|collision| WallSide::from_normal(collision.toi.normal1));
+        wall_contact.update(
+            touched_wall,
+            output.grounded || climber.climbing,
+            config.wall_contact_grace_ticks,
+        );
+
+        // bobbing at the surface counts as ground so the player can jump out of the water
+        let swimming = submerged.is_some() && !climber.climbing;
+        let surfaced = submerged.is_some_and(|submerged| !submerged.head_under);
+        jump_grace.tick(
+            output.grounded || climber.climbing || surfaced,
+            buffered.just_pressed(Action::Jump),
+        );
+
+        platform_drop.ticks = platform_drop.ticks.saturating_sub(1);
+        let on_platform =
+            output.grounded && standing_on(output).any(|ground| platforms.contains(ground));
+        if on_platform && axes.vertical < 0. && jump_grace.take_jump_press(&config) {
+            platform_drop.ticks = config.platform_drop_ticks;
+        }
+
+        if let Some(speed) = launch.vertical_speed.take() {
+            velocity.0 = speed;
+            jump_grace.jumping = false;
+            climber.climbing = false;
         }
 
-        if (input.just_pressed(KeyCode::Space) || input.just_pressed(KeyCode::Up))
-            && (output.grounded || climber.climbing)
+        if dash.is_dashing() {
+            velocity.0 = 0.;
+        } else if jump_grace.try_jump(&config) {
+            velocity.0 = config.jump_speed;
+            jump_grace.jumping = true;
+            climber.climbing = false;
+        } else if climber.climbing {
+            velocity.0 = 0.;
+            transition_vector.y = axes.vertical * config.climb_speed * delta;
+            if let Some(ladder) = ladder.and_then(|ladder| ladders.get(ladder).ok()) {
+                transition_vector.x = ladder.translation().x - position.x;
+            }
+        } else if swimming {
+            velocity.0 = (velocity.0 + config.gravity * config.water_gravity_scale * delta)
+                .max(-config.water_terminal_velocity);
+            let stroke = if jump_held { 1. } else { axes.vertical };
+            if stroke > 0. {
+                velocity.0 = velocity.0.max(stroke * config.swim_speed);
+            } else if stroke < 0. {
+                velocity.0 = velocity.0.min(stroke * config.swim_speed);
+            }
+        } else if let Some(side) = wall_contact
+            .side
+            .filter(|_| jump_grace.take_jump_press(&config))
         {
-            transition_vector.y += 10.;
+            let away = match side {
+                WallSide::Left => 1.,
+                WallSide::Right => -1.,
+            };
+            velocity.0 = config.wall_jump_vertical_speed;
+            jump_grace.jumping = true;
+            *impulse = HorizontalImpulse {
+                speed: away * config.wall_jump_horizontal_speed,
+                ticks: config.wall_jump_lock_ticks,
+            };
+            wall_contact.side = None;
         } else {
-            if !climber.climbing {
-                transition_vector.y -= 1.;
+            velocity.0 = (velocity.0 + config.gravity * delta).max(-config.terminal_velocity);
+
+            let pushing_into_wall = match wall_contact.side {
+                Some(WallSide::Left) => axes.horizontal < 0.,
+                Some(WallSide::Right) => axes.horizontal > 0.,
+                None => false,
+            };
+            if pushing_into_wall {
+                velocity.0 = velocity.0.max(-config.wall_slide_speed);
             }
         }
-        let clamped_vector = transition_vector.clamp(Vec2::new(-6., -6.), Vec2::new(6., 6.));
-        controller.translation = Some(clamped_vector);
+
+        // the jump ends at its apex or on landing, launches from anything else are never cut
+        if velocity.0 <= 0. {
+            jump_grace.jumping = false;
+        }
+        // releasing jump early cuts the ascent short
+        if jump_grace.jumping && !jump_held && !swimming {
+            velocity.0 = velocity.0.min(config.jump_cut_speed);
+            jump_grace.jumping = false;
+        }
+
+        // one-way platforms only block the player while falling onto them from above
+        let platforms_solid =
+            platform_drop.ticks == 0 && velocity.0 <= 0. && !climber.climbing && !inside_platform;
+        let mut filter_groups: CollisionGroups = GameCollisions::Player.into();
+        if !platforms_solid {
+            filter_groups
+                .filters
+                .remove(GameCollisions::Platform.into());
+        }
+        controller.filter_groups = Some(filter_groups);
+
+        transition_vector.y += velocity.0 * delta;
+
+        if let Some(platform_velocity) =
+            standing_on(output).find_map(|ground| moving_platforms.get(ground).ok())
+        {
+            transition_vector += platform_velocity.linvel * delta;
+        }
+
+        controller.translation = Some(transition_vector);
     }
 }
 
@@ -216,6 +623,19 @@ pub struct PlayerBundle {
     pub creature_bundle: CreatureBundle,
     pub player: Player,
     pub climber: Climber,
+    pub jump_grace: JumpGrace,
+    pub vertical_velocity: VerticalVelocity,
+    pub wall_contact: WallContact,
+    pub horizontal_impulse: HorizontalImpulse,
+    pub pending_launch: PendingLaunch,
+    pub dash: Dash,
+    pub platform_drop: PlatformDrop,
+    pub breath: Breath,
+    pub mana: Mana,
+    pub invulnerability: Invulnerability,
+    pub crouch: Crouch,
+    pub swinger: Swinger,
+    pub aseprite_animation: AsepriteAnimation,
 }
 
 impl LdtkEntity for PlayerBundle {
@@ -224,10 +644,9 @@ impl LdtkEntity for PlayerBundle {
         _layer_instance: &bevy_ecs_ldtk::prelude::LayerInstance,
         tileset: Option<&Handle<Image>>,
         tileset_definition: Option<&bevy_ecs_ldtk::prelude::TilesetDefinition>,
-        _asset_server: &AssetServer,
+        asset_server: &AssetServer,
         texture_atlases: &mut Assets<TextureAtlas>,
     ) -> Self {
-        let animation_indices = AnimationIndices { first: 0, last: 3 };
         let tileset_definition = tileset_definition.unwrap();
         let texture_atlas = TextureAtlas::from_grid(
             tileset.unwrap().clone(),
@@ -253,16 +672,30 @@ impl LdtkEntity for PlayerBundle {
 
         Self {
             climber: Climber::default(),
+            jump_grace: JumpGrace::default(),
+            vertical_velocity: VerticalVelocity::default(),
+            wall_contact: WallContact::default(),
+            horizontal_impulse: HorizontalImpulse::default(),
+            pending_launch: PendingLaunch::default(),
+            dash: Dash::default(),
+            platform_drop: PlatformDrop::default(),
+            breath: Breath::default(),
+            mana: Mana::default(),
+            invulnerability: Invulnerability::default(),
+            crouch: Crouch::default(),
+            swinger: Swinger::default(),
+            aseprite_animation: AsepriteAnimation(asset_server.load("atlas/player.aseprite.json")),
             creature_bundle: CreatureBundle {
-                animation_bundle: AnimationBundle {
-                    animation_timer: AnimationTimer(Timer::from_seconds(0.2, TimerMode::Repeating)),
-                    animation_indices,
-                },
+                animation_bundle: player_animation(),
                 health: Health(100),
-                damage: Damage(20),
+                max_health: MaxHealth(100),
+                damage: Damage::new(20, DamageKind::Physical),
                 sprite: sprite_sheet_bundle,
                 character_controller: KinematicCharacterController {
                     slide: true,
+                    max_slope_climb_angle: 46_f32.to_radians(),
+                    min_slope_slide_angle: 50_f32.to_radians(),
+                    snap_to_ground: Some(CharacterLength::Absolute(6.)),
                     apply_impulse_to_dynamic_bodies: true,
                     filter_flags: QueryFilterFlags::from_bits(24).unwrap(),
                     filter_groups: Some(GameCollisions::Player.into()),
@@ -270,7 +703,7 @@ impl LdtkEntity for PlayerBundle {
                 },
                 collider_bundle: ColliderBundle {
                     rigid_body: RigidBody::KinematicVelocityBased,
-                    collider: Collider::cuboid(4., 8.),
+                    collider: Collider::cuboid(STANDING_HALF_EXTENTS.x, STANDING_HALF_EXTENTS.y),
                     collision_groups: GameCollisions::Player.into(),
                     rotation_constraints: LockedAxes::ROTATION_LOCKED,
                     ..Default::default()
@@ -1,14 +1,19 @@
-use std::collections::VecDeque;
+use std::{collections::VecDeque, f32::consts::FRAC_PI_4};
 
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
 use crate::{
-    creature::{Damage, Health},
+    camera::CursorWorldPosition,
+    collisions::GameCollisions,
+    controls::Action,
+    creature::{Damage, DamageEvent, DamageKind, Mana},
     enemy::Enemy,
-    items::EntitiesResource,
-    particles::spawn_splash_particles,
+    inventory::Inventory,
+    items::{EntitiesResource, Item, ItemType},
+    map::{Slope, Wall},
     player::Player,
+    status::{Inflicts, StatusEffect, StatusEvent, StatusKind},
 };
 
 #[derive(Clone, Debug, Default, Bundle)]
@@ -22,89 +27,237 @@ pub struct ProjectileBundle {
     pub gravity_scale: GravityScale,
     pub friction: Friction,
     pub density: ColliderMassProperties,
+    pub collision_groups: CollisionGroups,
     pub damage: Damage,
 }
 
 #[derive(Component, Default, Debug, Clone)]
 pub struct Projectile;
 
+/// Projectile that sticks into walls and can be picked up again
+#[derive(Component, Default, Debug, Clone)]
+pub struct Arrow;
+
+/// Item the arrows stuck in walls turn into
+pub const ARROW_ITEM: &str = "Arrow";
+
 #[derive(Resource, Default)]
 pub struct ProjectilesGlobalAmount {
     pub queue: VecDeque<Entity>,
 }
 
 const MAX_PROJECTILES: usize = 1000;
+const FIREBALL_MANA_COST: f32 = 20.;
+/// Seconds an enemy hit by a fireball keeps burning
+const FIREBALL_BURN_SECONDS: f32 = 3.;
+/// The arrow tile points slightly up and to the right
+const ARROW_TILE_ANGLE: f32 = 0.25;
+/// Speed enemies are pushed along the ground with by a hit
+const PROJECTILE_KNOCKBACK: f32 = 100.;
+
+/// Bends shots towards the nearest enemy inside a cone around the aim direction
+#[derive(Resource, Debug)]
+pub struct AimAssist {
+    pub enabled: bool,
+    /// Half of the cone angle in radians
+    pub half_angle: f32,
+    pub range: f32,
+}
+
+impl Default for AimAssist {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            half_angle: 12_f32.to_radians(),
+            range: 300.,
+        }
+    }
+}
+
+impl AimAssist {
+    fn snap<'a>(
+        &self,
+        origin: Vec2,
+        direction: Vec2,
+        targets: impl Iterator<Item = &'a GlobalTransform>,
+    ) -> Vec2 {
+        if !self.enabled {
+            return direction;
+        }
+        targets
+            .map(|target| target.translation().truncate() - origin)
+            .filter(|to_target| {
+                to_target.length() <= self.range
+                    && direction.angle_between(*to_target).abs() <= self.half_angle
+            })
+            .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+            .map(|to_target| to_target.normalize())
+            .unwrap_or(direction)
+    }
+}
+
+/// Direction from the origin to the cursor, or where the shooter is facing without a cursor
+pub fn aim_direction(cursor: Option<Vec2>, origin: Vec2, facing_left: bool) -> Vec2 {
+    let facing = if facing_left { Vec2::NEG_X } else { Vec2::X };
+    cursor
+        .map(|cursor| (cursor - origin).normalize_or_zero())
+        .filter(|direction| *direction != Vec2::ZERO)
+        .unwrap_or(facing)
+}
 
 pub fn despawn_projectiles(
     mut commands: Commands,
     mut projectiles_amount: ResMut<ProjectilesGlobalAmount>,
 ) {
     if projectiles_amount.queue.len() > MAX_PROJECTILES {
-        if let Some(entity) = projectiles_amount.queue.pop_back() {
-            commands.entity(entity).despawn();
+        // projectiles that broke on a hit are gone already
+        if let Some(mut entity) = projectiles_amount
+            .queue
+            .pop_back()
+            .and_then(|entity| commands.get_entity(entity))
+        {
+            entity.despawn();
         }
     }
 }
 
+/// Projectiles hurt the enemies they touch and break on anything else,
+/// arrows stick into the level instead
+#[allow(clippy::too_many_arguments)]
 pub fn projectiles_collisions(
     rapier_context: Res<RapierContext>,
     mut commands: Commands,
-    projectiles: Query<(Entity, &Damage), With<Projectile>>,
-    mut enemies: Query<(Entity, &mut Health, &Transform), With<Enemy>>,
+    mut projectiles_amount: ResMut<ProjectilesGlobalAmount>,
+    projectiles: Query<
+        (
+            Entity,
+            &Damage,
+            &Velocity,
+            Option<&Arrow>,
+            Option<&Inflicts>,
+        ),
+        With<Projectile>,
+    >,
+    enemies: Query<Entity, With<Enemy>>,
+    level: Query<(), Or<(With<Wall>, With<Slope>)>>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut status_events: EventWriter<StatusEvent>,
 ) {
-    for (projectile, Damage(damage)) in &projectiles {
-        for (enemy, mut enemy_health, enemy_position) in &mut enemies {
-            if let Some(contact) = rapier_context.contact_pair(projectile, enemy) {
-                if let Some((_, view)) = contact.find_deepest_contact() {
-                    let contact = view.local_p1();
-                    let mut position = enemy_position.clone();
-                    let mut impulse = ExternalImpulse::default();
-                    if contact.x > 0. {
-                        position.translation.x -= 10.;
-                        impulse.impulse = Vec2::new(-1000.0, 0.);
-                    } else {
-                        position.translation.x += 10.;
-                        impulse.impulse = Vec2::new(1000.0, 0.);
-                    };
-
-                    spawn_splash_particles(&mut commands, 10, position, Some(impulse));
-                    enemy_health.0 -= damage;
+    for (projectile, damage, velocity, arrow, inflicts) in &projectiles {
+        let direction = velocity.linvel.normalize_or_zero();
+        let mut hit_enemy = false;
+        for enemy in &enemies {
+            if rapier_context
+                .contact_pair(projectile, enemy)
+                .is_some_and(|contact| contact.has_any_active_contacts())
+            {
+                hit_enemy = true;
+                damage_events.send(DamageEvent {
+                    target: enemy,
+                    source: Some(projectile),
+                    amount: damage.amount,
+                    kind: damage.kind,
+                    // enemies walk on the ground, they aren't lifted by the hit
+                    knockback: Some(Vec2::new(direction.x * PROJECTILE_KNOCKBACK, 0.)),
+                    direction: Some(direction),
+                });
+                if let Some(Inflicts(effect)) = inflicts {
+                    status_events.send(StatusEvent {
+                        target: enemy,
+                        effect: effect.clone(),
+                    });
                 }
             }
         }
-        if let Some(_contact) = rapier_context.contacts_with(projectile).next() {
+        if hit_enemy {
+            commands.entity(projectile).despawn();
+            continue;
+        }
+        let touching: Vec<Entity> = rapier_context
+            .contacts_with(projectile)
+            .filter(|contact| contact.has_any_active_contacts())
+            .map(|contact| {
+                if contact.collider1() == projectile {
+                    contact.collider2()
+                } else {
+                    contact.collider1()
+                }
+            })
+            .collect();
+        if touching.is_empty() {
+            continue;
+        }
+        if arrow.is_some() && touching.iter().any(|other| level.contains(*other)) {
+            stick_arrow(&mut commands, projectile);
+            // stuck arrows are items the player picks up, the projectile limit leaves them be
+            projectiles_amount
+                .queue
+                .retain(|entity| *entity != projectile);
+        } else {
             commands.entity(projectile).despawn();
         }
     }
 }
 
+/// Turns a flying arrow into an item lodged where it hit
+fn stick_arrow(commands: &mut Commands, arrow: Entity) {
+    let collision_groups: CollisionGroups = GameCollisions::Item.into();
+    commands
+        .entity(arrow)
+        .remove::<(Projectile, Damage, Ccd)>()
+        .insert((
+            RigidBody::Fixed,
+            Sensor,
+            ActiveCollisionTypes::default() | ActiveCollisionTypes::KINEMATIC_STATIC,
+            collision_groups,
+            Item(ARROW_ITEM.to_owned()),
+            ItemType::CraftMaterial,
+        ));
+}
+
+pub fn arrow_rotation(direction: Vec2) -> Quat {
+    Quat::from_rotation_z(direction.y.atan2(direction.x) - ARROW_TILE_ANGLE)
+}
+
+/// Points flying arrows along their velocity
+pub fn orient_arrows(
+    mut arrows: Query<(&Velocity, &mut Transform), (With<Arrow>, With<Projectile>)>,
+) {
+    for (velocity, mut transform) in &mut arrows {
+        if velocity.linvel != Vec2::ZERO {
+            transform.rotation = arrow_rotation(velocity.linvel);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn shoot_projectile(
-    input: Res<Input<MouseButton>>,
+    actions: Res<Input<Action>>,
     assets: Res<EntitiesResource>,
+    cursor_position: Res<CursorWorldPosition>,
+    aim_assist: Res<AimAssist>,
+    inventory: Res<Inventory>,
     mut projectiles_amount: ResMut<ProjectilesGlobalAmount>,
     mut commands: Commands,
-    player_query: Query<(&Transform, &TextureAtlasSprite), With<Player>>,
+    mut player_query: Query<(&Transform, &TextureAtlasSprite, &mut Mana), With<Player>>,
+    enemies: Query<&GlobalTransform, With<Enemy>>,
 ) {
-    for (origin_transform, origin_sprite) in &player_query {
-        if input.just_pressed(MouseButton::Left) {
-            let is_flipped = origin_sprite.flip_x;
+    // weapons replace the fireball
+    if inventory.selected_weapon().is_some() {
+        return;
+    }
+    for (origin_transform, origin_sprite, mut mana) in &mut player_query {
+        if actions.just_pressed(Action::Attack) && mana.spend(FIREBALL_MANA_COST) {
+            let player_position = origin_transform.translation.truncate();
+            let direction = aim_direction(cursor_position.0, player_position, origin_sprite.flip_x);
+            let direction = aim_assist.snap(player_position, direction, enemies.iter());
 
             let strength = 800.;
-            let impulse = if is_flipped {
-                Vec2::new(-strength, 0.)
-            } else {
-                Vec2::new(strength, 0.)
-            };
-
-            let mut origin = Vec3::from(origin_transform.translation);
-            origin.x += match is_flipped {
-                true => -10.,
-                false => 10.,
-            };
-            let rotation = match is_flipped {
-                true => Quat::from_rotation_z(0.7853982),
-                false => Quat::from_rotation_z(-2.356194),
-            };
+            let impulse = direction * strength;
+
+            let origin = (player_position + direction * 10.).extend(origin_transform.translation.z);
+            // the fireball tile points up and to the left
+            let rotation = Quat::from_rotation_z(direction.y.atan2(direction.x) - 3. * FRAC_PI_4);
 
             let fireball = assets.entities.get("FireBall").expect("fireball to exist");
             projectiles_amount.queue.push_front(
@@ -116,13 +269,15 @@ pub fn shoot_projectile(
                             density: ColliderMassProperties::Mass(1.0),
                             gravity_scale: GravityScale(0.2),
                             ccd: Ccd::enabled(),
-                            damage: Damage(20),
+                            collision_groups: GameCollisions::Projectile.into(),
+                            damage: Damage::new(20, DamageKind::Fire),
                             ..Default::default()
                         },
                         ExternalImpulse {
                             impulse,
                             torque_impulse: 0.0,
                         },
+                        Inflicts(StatusEffect::new(StatusKind::Burn, FIREBALL_BURN_SECONDS)),
                         SpriteSheetBundle {
                             texture_atlas: fireball.texture_atlas.clone(),
                             sprite: TextureAtlasSprite::new(fireball.index),
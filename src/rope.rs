@@ -0,0 +1,218 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    collisions::GameCollisions,
+    config::MovementConfig,
+    controls::{Action, BufferedActions, MovementAxes},
+    player::{HorizontalImpulse, Player, VerticalVelocity},
+};
+
+/// How close the player has to be to a segment to grab it
+const GRAB_DISTANCE: f32 = 10.;
+/// Mass the hanging player adds to the grabbed segment
+const SWINGER_MASS: f32 = 2.;
+const ROPE_WIDTH: f32 = 3.;
+/// Upper bound for the editable `segments` field, every segment is a joint to simulate
+const MAX_SEGMENTS: i32 = 32;
+
+/// Anchor of a rope or chain hanging from level geometry
+#[derive(Component, Debug)]
+pub struct Swingable {
+    pub segments: usize,
+    pub segment_length: f32,
+}
+
+impl Default for Swingable {
+    fn default() -> Self {
+        Self {
+            segments: 6,
+            segment_length: 8.,
+        }
+    }
+}
+
+/// Link of a swingable, jointed to the previous link or the anchor
+#[derive(Component, Default, Debug)]
+pub struct RopeSegment;
+
+/// Rope segment the player is hanging from
+#[derive(Component, Default, Debug)]
+pub struct Swinger {
+    pub segment: Option<Entity>,
+}
+
+#[derive(Bundle, Default)]
+pub struct SwingableBundle {
+    pub swingable: Swingable,
+    pub rigid_body: RigidBody,
+}
+
+impl LdtkEntity for SwingableBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        _layer_instance: &LayerInstance,
+        _tileset: Option<&Handle<Image>>,
+        _tileset_definition: Option<&TilesetDefinition>,
+        _asset_server: &AssetServer,
+        _texture_atlases: &mut Assets<TextureAtlas>,
+    ) -> Self {
+        let default = Swingable::default();
+        let segments = entity_instance
+            .get_int_field("segments")
+            .map(|&segments| {
+                let clamped = segments.clamp(1, MAX_SEGMENTS);
+                if clamped != segments {
+                    warn!("swingable with {} segments, using {}", segments, clamped);
+                }
+                clamped as usize
+            })
+            .unwrap_or(default.segments);
+        // the capsule of a segment needs room for its rounded ends
+        let segment_length = entity_instance
+            .get_float_field("segment_length")
+            .copied()
+            .unwrap_or(default.segment_length)
+            .max(ROPE_WIDTH * 2.);
+
+        Self {
+            swingable: Swingable {
+                segments,
+                segment_length,
+            },
+            rigid_body: RigidBody::Fixed,
+        }
+    }
+}
+
+/// Hangs a chain of jointed dynamic segments under every new swingable anchor
+pub fn spawn_rope_segments(
+    mut commands: Commands,
+    swingables: Query<(Entity, &Swingable), Added<Swingable>>,
+) {
+    for (anchor, swingable) in &swingables {
+        let half_length = swingable.segment_length / 2.;
+        let collision_groups = CollisionGroups::new(
+            GameCollisions::Environment.into(),
+            GameCollisions::Wall.into(),
+        );
+
+        commands.entity(anchor).with_children(|parent| {
+            let mut previous = anchor;
+            for i in 0..swingable.segments {
+                // the first segment hangs from the anchor point itself
+                let parent_anchor = if i == 0 {
+                    Vec2::ZERO
+                } else {
+                    Vec2::new(0., -half_length)
+                };
+                let joint = RevoluteJointBuilder::new()
+                    .local_anchor1(parent_anchor)
+                    .local_anchor2(Vec2::new(0., half_length));
+
+                previous = parent
+                    .spawn((
+                        RopeSegment,
+                        RigidBody::Dynamic,
+                        Collider::capsule_y(half_length - ROPE_WIDTH / 2., ROPE_WIDTH / 2.),
+                        ColliderMassProperties::Mass(1.),
+                        collision_groups,
+                        ImpulseJoint::new(previous, joint),
+                        Velocity::default(),
+                        ExternalForce::default(),
+                        Damping {
+                            linear_damping: 0.1,
+                            angular_damping: 0.5,
+                        },
+                        SpriteBundle {
+                            sprite: Sprite {
+                                color: Color::rgb(0.55, 0.4, 0.25),
+                                custom_size: Some(Vec2::new(ROPE_WIDTH, swingable.segment_length)),
+                                ..default()
+                            },
+                            transform: Transform::from_xyz(
+                                0.,
+                                -(i as f32 + 0.5) * swingable.segment_length,
+                                0.,
+                            ),
+                            ..default()
+                        },
+                    ))
+                    .id();
+            }
+        });
+    }
+}
+
+/// Grabbing, pumping and letting go of ropes
+///
+/// Runs after `movement` and overrides its result while the player hangs on a rope.
+pub fn swing(
+    actions: Res<BufferedActions>,
+    axes: Res<MovementAxes>,
+    mut players: Query<
+        (
+            &mut Swinger,
+            &mut KinematicCharacterController,
+            &mut VerticalVelocity,
+            &mut HorizontalImpulse,
+            &GlobalTransform,
+        ),
+        With<Player>,
+    >,
+    mut segments: Query<
+        (Entity, &GlobalTransform, &Velocity, &mut ExternalForce),
+        With<RopeSegment>,
+    >,
+    config: Res<MovementConfig>,
+) {
+    for (mut swinger, mut controller, mut velocity, mut impulse, transform) in &mut players {
+        let position = transform.translation().truncate();
+
+        if swinger.segment.is_none() && actions.just_pressed(Action::ClimbUp) {
+            swinger.segment = segments
+                .iter()
+                .map(|(segment, segment_transform, ..)| {
+                    (
+                        segment,
+                        segment_transform
+                            .translation()
+                            .truncate()
+                            .distance(position),
+                    )
+                })
+                .filter(|(_, distance)| *distance < GRAB_DISTANCE)
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(segment, _)| segment);
+        }
+
+        let Some(segment) = swinger.segment else {
+            continue;
+        };
+        let Ok((_, segment_transform, segment_velocity, mut force)) = segments.get_mut(segment)
+        else {
+            swinger.segment = None;
+            continue;
+        };
+
+        if actions.just_pressed(Action::Jump) {
+            // keep the momentum of the swing
+            force.force = Vec2::ZERO;
+            velocity.0 = segment_velocity.linvel.y.max(0.) + config.jump_speed;
+            *impulse = HorizontalImpulse {
+                speed: segment_velocity.linvel.x,
+                ticks: config.swing_release_ticks,
+            };
+            swinger.segment = None;
+            continue;
+        }
+
+        force.force = Vec2::new(
+            axes.horizontal * config.swing_pump_force,
+            config.gravity * SWINGER_MASS,
+        );
+        velocity.0 = 0.;
+        controller.translation = Some(segment_transform.translation().truncate() - position);
+    }
+}
@@ -0,0 +1,361 @@
+use bevy::prelude::*;
+
+use crate::creature::{DamageEvent, DamageKind, Health, MaxHealth, Resistances};
+
+/// Seconds between two ticks of damage or healing
+const STATUS_TICK_SECONDS: f32 = 1.;
+/// Poison applied again on a poisoned creature adds up to this many stacks
+const POISON_MAX_STACKS: u32 = 3;
+const POISON_DAMAGE: f32 = 3.;
+const BURN_DAMAGE: f32 = 5.;
+const REGENERATION_HEAL: f32 = 4.;
+/// Share of the movement speed a slowed creature keeps
+const SLOW_MULTIPLIER: f32 = 0.5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StatusKind {
+    Poison,
+    Burn,
+    Slow,
+    Regeneration,
+}
+
+impl StatusKind {
+    /// Effect named by the field of a consumable entity, unknown names are logged
+    pub fn from_identifier(identifier: &str) -> Option<Self> {
+        match identifier {
+            "Poison" => Some(Self::Poison),
+            "Burn" => Some(Self::Burn),
+            "Slow" => Some(Self::Slow),
+            "Regeneration" => Some(Self::Regeneration),
+            _ => {
+                warn!(
+                    "skipping consumable {identifier}, its field should be Heal, Damage or a status effect"
+                );
+                None
+            }
+        }
+    }
+
+    /// Poison stacks, every other effect only refreshes its duration
+    pub fn max_stacks(self) -> u32 {
+        match self {
+            Self::Poison => POISON_MAX_STACKS,
+            _ => 1,
+        }
+    }
+
+    /// Kind of the damage dealt every tick, creatures immune to it can't get the effect
+    pub fn damage_kind(self) -> Option<DamageKind> {
+        match self {
+            Self::Poison => Some(DamageKind::Poison),
+            Self::Burn => Some(DamageKind::Fire),
+            Self::Slow | Self::Regeneration => None,
+        }
+    }
+
+    /// Damage or healing per tick and stack, unused by slow
+    pub fn potency(self) -> f32 {
+        match self {
+            Self::Poison => POISON_DAMAGE,
+            Self::Burn => BURN_DAMAGE,
+            Self::Regeneration => REGENERATION_HEAL,
+            Self::Slow => 0.,
+        }
+    }
+
+    /// Index of the effect icon in the icons atlas
+    pub fn icon(self) -> usize {
+        match self {
+            Self::Poison => 172,
+            Self::Burn => 234,
+            Self::Slow => 169,
+            Self::Regeneration => 108,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub stacks: u32,
+    pub duration: Timer,
+    pub tick: Timer,
+}
+
+impl StatusEffect {
+    pub fn new(kind: StatusKind, seconds: f32) -> Self {
+        Self {
+            kind,
+            stacks: 1,
+            duration: Timer::from_seconds(seconds, TimerMode::Once),
+            tick: Timer::from_seconds(STATUS_TICK_SECONDS, TimerMode::Repeating),
+        }
+    }
+}
+
+/// Timed effects a creature currently suffers or enjoys
+#[derive(Component, Default, Clone, Debug)]
+pub struct StatusEffects(pub Vec<StatusEffect>);
+
+impl StatusEffects {
+    /// Adds a stack to an effect of the same kind and keeps the longer of both durations,
+    /// otherwise starts the effect
+    pub fn apply(&mut self, effect: StatusEffect) {
+        let Some(current) = self
+            .0
+            .iter_mut()
+            .find(|current| current.kind == effect.kind)
+        else {
+            self.0.push(effect);
+            return;
+        };
+        current.stacks = (current.stacks + effect.stacks).min(current.kind.max_stacks());
+        if effect.duration.remaining() > current.duration.remaining() {
+            current.duration = effect.duration;
+        }
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.0.iter().any(|effect| effect.kind == kind)
+    }
+
+    /// Factor applied to the movement speed of the creature
+    pub fn speed_multiplier(&self) -> f32 {
+        if self.has(StatusKind::Slow) {
+            SLOW_MULTIPLIER
+        } else {
+            1.
+        }
+    }
+}
+
+/// Request to put a timed effect on a creature
+#[derive(Event, Clone, Debug)]
+pub struct StatusEvent {
+    pub target: Entity,
+    pub effect: StatusEffect,
+}
+
+/// Status effect a projectile puts on the creatures it hits
+#[derive(Component, Clone, Debug)]
+pub struct Inflicts(pub StatusEffect);
+
+pub fn apply_status_effects(
+    mut events: EventReader<StatusEvent>,
+    mut targets: Query<(&mut StatusEffects, Option<&Resistances>)>,
+) {
+    for event in events.iter() {
+        let Ok((mut effects, resistances)) = targets.get_mut(event.target) else {
+            continue;
+        };
+        let immune = event
+            .effect
+            .kind
+            .damage_kind()
+            .zip(resistances)
+            .is_some_and(|(kind, resistances)| resistances.multiplier(kind) == 0.);
+        if !immune {
+            effects.apply(event.effect.clone());
+        }
+    }
+}
+
+/// Deals damage over time through damage events and heals directly,
+/// effects are removed once they run out
+pub fn tick_status_effects(
+    time: Res<Time>,
+    mut creatures: Query<(Entity, &mut StatusEffects, &mut Health, &MaxHealth)>,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    for (creature, mut effects, mut health, max_health) in &mut creatures {
+        if effects.0.is_empty() {
+            continue;
+        }
+        for effect in effects.0.iter_mut() {
+            effect.duration.tick(time.delta());
+            if !effect.tick.tick(time.delta()).just_finished() {
+                continue;
+            }
+            let amount = (effect.kind.potency() * effect.stacks as f32).round() as isize;
+            if effect.kind == StatusKind::Regeneration {
+                health.heal(amount, max_health);
+            } else if let Some(kind) = effect.kind.damage_kind() {
+                damage_events.send(DamageEvent {
+                    target: creature,
+                    source: None,
+                    amount,
+                    kind,
+                    knockback: None,
+                    direction: None,
+                });
+            }
+        }
+        effects.0.retain(|effect| !effect.duration.finished());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::utils::HashMap;
+
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_event::<StatusEvent>()
+            .add_event::<DamageEvent>()
+            .init_resource::<Time>()
+            .add_systems(Update, (apply_status_effects, tick_status_effects).chain());
+        // the first update of the clock has no delta
+        advance(&mut app, 0.);
+        app
+    }
+
+    /// Runs one update that lasts the given amount of seconds
+    fn advance(app: &mut App, seconds: f32) {
+        let mut time = app.world.resource_mut::<Time>();
+        let last = time.last_update().unwrap_or_else(|| time.startup());
+        time.update_with_instant(last + Duration::from_secs_f32(seconds));
+        app.update();
+    }
+
+    fn creature(app: &mut App, health: isize) -> Entity {
+        app.world
+            .spawn((StatusEffects::default(), Health(health), MaxHealth(100)))
+            .id()
+    }
+
+    fn effects(app: &App, creature: Entity) -> &[StatusEffect] {
+        &app.world.get::<StatusEffects>(creature).unwrap().0
+    }
+
+    fn damage_events(app: &App) -> Vec<DamageEvent> {
+        app.world
+            .resource::<Events<DamageEvent>>()
+            .iter_current_update_events()
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn poison_stacks_up_to_its_limit_and_keeps_the_longer_duration() {
+        let mut app = app();
+        let creature = creature(&mut app, 100);
+        for seconds in [5., 2., 2., 2.] {
+            app.world.send_event(StatusEvent {
+                target: creature,
+                effect: StatusEffect::new(StatusKind::Poison, seconds),
+            });
+        }
+        advance(&mut app, 0.);
+
+        let [poison] = effects(&app, creature) else {
+            panic!("poison should be a single effect");
+        };
+        assert_eq!(poison.stacks, POISON_MAX_STACKS);
+        assert_eq!(poison.duration.duration(), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn slow_refreshes_without_stacking() {
+        let mut app = app();
+        let creature = creature(&mut app, 100);
+        app.world.send_event(StatusEvent {
+            target: creature,
+            effect: StatusEffect::new(StatusKind::Slow, 2.),
+        });
+        advance(&mut app, 0.);
+        advance(&mut app, 1.5);
+        app.world.send_event(StatusEvent {
+            target: creature,
+            effect: StatusEffect::new(StatusKind::Slow, 2.),
+        });
+        advance(&mut app, 0.);
+
+        let [slow] = effects(&app, creature) else {
+            panic!("slow should be a single effect");
+        };
+        assert_eq!(slow.stacks, 1);
+        assert_eq!(slow.duration.remaining(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn ticks_deal_potency_times_stacks() {
+        let mut app = app();
+        let creature = creature(&mut app, 100);
+        for _ in 0..2 {
+            app.world.send_event(StatusEvent {
+                target: creature,
+                effect: StatusEffect::new(StatusKind::Poison, 5.),
+            });
+        }
+        advance(&mut app, 0.);
+        assert!(damage_events(&app).is_empty());
+
+        advance(&mut app, STATUS_TICK_SECONDS);
+        let events = damage_events(&app);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].target, creature);
+        assert_eq!(events[0].kind, DamageKind::Poison);
+        assert_eq!(events[0].amount, (POISON_DAMAGE * 2.) as isize);
+    }
+
+    #[test]
+    fn immune_creatures_ignore_effects() {
+        let mut app = app();
+        let creature = creature(&mut app, 100);
+        app.world
+            .entity_mut(creature)
+            .insert(Resistances(HashMap::from([(DamageKind::Fire, 0.)])));
+        app.world.send_event(StatusEvent {
+            target: creature,
+            effect: StatusEffect::new(StatusKind::Burn, 3.),
+        });
+        app.world.send_event(StatusEvent {
+            target: creature,
+            effect: StatusEffect::new(StatusKind::Poison, 3.),
+        });
+        advance(&mut app, 0.);
+
+        let kinds: Vec<StatusKind> = effects(&app, creature)
+            .iter()
+            .map(|effect| effect.kind)
+            .collect();
+        assert_eq!(kinds, vec![StatusKind::Poison]);
+    }
+
+    #[test]
+    fn expired_effects_are_removed() {
+        let mut app = app();
+        let creature = creature(&mut app, 100);
+        app.world.send_event(StatusEvent {
+            target: creature,
+            effect: StatusEffect::new(StatusKind::Slow, 1.5),
+        });
+        advance(&mut app, 0.);
+        advance(&mut app, 1.);
+        assert_eq!(effects(&app, creature).len(), 1);
+
+        advance(&mut app, 1.);
+        assert!(effects(&app, creature).is_empty());
+    }
+
+    #[test]
+    fn regeneration_stops_at_max_health() {
+        let mut app = app();
+        let creature = creature(&mut app, 95);
+        app.world.send_event(StatusEvent {
+            target: creature,
+            effect: StatusEffect::new(StatusKind::Regeneration, 5.),
+        });
+        advance(&mut app, 0.);
+        advance(&mut app, STATUS_TICK_SECONDS);
+        assert_eq!(app.world.get::<Health>(creature).unwrap().0, 99);
+
+        advance(&mut app, STATUS_TICK_SECONDS);
+        assert_eq!(app.world.get::<Health>(creature).unwrap().0, 100);
+    }
+}
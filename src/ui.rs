@@ -1,10 +1,21 @@
 use bevy::{prelude::*, ui::RelativeCursorPosition};
 
-use crate::{creature::Health, player::Player};
+use crate::{
+    creature::{Health, Mana},
+    inventory::Inventory,
+    items::WeaponType,
+    player::Player,
+    projectile::ARROW_ITEM,
+    status::StatusEffects,
+    water::Breath,
+};
 
 pub const BACKGROUND_COLOR: Color = Color::INDIGO;
 pub const SELECTED_COLOR: Color = Color::WHITE;
 pub const TRANSPARENT: Color = Color::rgba(0., 0., 0., 0.);
+pub const HOTBAR_SLOTS: usize = 4;
+/// One icon per kind of status effect
+pub const STATUS_SLOTS: usize = 4;
 
 #[derive(Debug, Component, Default)]
 pub struct Ui;
@@ -12,6 +23,18 @@ pub struct Ui;
 #[derive(Debug, Component, Default)]
 pub struct HealthIndicator;
 
+/// Mana of the player, or the arrows left while a bow is selected
+#[derive(Debug, Component, Default)]
+pub struct ResourceIndicator;
+
+/// Air left while the player is under water, empty otherwise
+#[derive(Debug, Component, Default)]
+pub struct BreathIndicator;
+
+/// Icon of the active status effect at this position
+#[derive(Debug, Component)]
+pub struct StatusIndicator(pub usize);
+
 #[derive(Debug, Component, Default)]
 pub struct InventoryImageIndicator;
 
@@ -40,6 +63,65 @@ pub fn update_health_ui(
     }
 }
 
+pub fn update_resource_ui(
+    player_mana: Query<Ref<Mana>, With<Player>>,
+    inventory: Res<Inventory>,
+    mut resource_ui: Query<&mut Text, With<ResourceIndicator>>,
+) {
+    if let Ok(mana) = player_mana.get_single() {
+        if !mana.is_changed() && !inventory.is_changed() {
+            return;
+        }
+        if let Ok(mut text) = resource_ui.get_single_mut() {
+            if let Some(section) = text.sections.first_mut() {
+                let bow_selected = inventory
+                    .selected_weapon()
+                    .is_some_and(|weapon| weapon.weapon_type == WeaponType::Bow);
+                section.value = if bow_selected {
+                    format!("  Arrows {}", inventory.count(ARROW_ITEM))
+                } else {
+                    format!("  Mp {}", mana.current as isize)
+                };
+            }
+        }
+    }
+}
+
+pub fn update_breath_ui(
+    player_breath: Query<&Breath, (With<Player>, Changed<Breath>)>,
+    mut breath_ui: Query<&mut Text, With<BreathIndicator>>,
+) {
+    if let Ok(breath) = player_breath.get_single() {
+        if let Ok(mut text) = breath_ui.get_single_mut() {
+            if let Some(section) = text.sections.first_mut() {
+                let fraction = breath.fraction_left();
+                section.value = if fraction < 1. {
+                    format!("  Air {}%", (fraction * 100.).ceil())
+                } else {
+                    String::new()
+                };
+            }
+        }
+    }
+}
+
+pub fn update_status_ui(
+    player_effects: Query<&StatusEffects, (With<Player>, Changed<StatusEffects>)>,
+    mut status_ui: Query<(&StatusIndicator, &mut UiTextureAtlasImage, &mut Visibility)>,
+) {
+    if let Ok(effects) = player_effects.get_single() {
+        for (StatusIndicator(i), mut image, mut visibility) in &mut status_ui {
+            match effects.0.get(*i) {
+                Some(effect) => {
+                    image.index = effect.kind.icon();
+                    *visibility = Visibility::Inherited;
+                }
+                None => *visibility = Visibility::Hidden,
+            }
+        }
+    }
+}
+
 pub fn setup_ui(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
@@ -125,6 +207,30 @@ pub fn setup_ui(
                 TextBundle::from_section("", text_style.to_owned()),
                 HealthIndicator,
             ));
+            parent.spawn((
+                TextBundle::from_section("", text_style.to_owned()),
+                ResourceIndicator,
+            ));
+            parent.spawn((
+                TextBundle::from_section("", text_style.to_owned()),
+                BreathIndicator,
+            ));
+            for i in 0..STATUS_SLOTS {
+                parent.spawn((
+                    AtlasImageBundle {
+                        style: Style {
+                            width: Val::Px(24.),
+                            height: Val::Px(24.),
+                            margin: UiRect::left(Val::Px(4.)),
+                            ..default()
+                        },
+                        texture_atlas: texture_atlas.clone(),
+                        visibility: Visibility::Hidden,
+                        ..Default::default()
+                    },
+                    StatusIndicator(i),
+                ));
+            }
         });
 
     commands
@@ -140,7 +246,7 @@ pub fn setup_ui(
             ..default()
         })
         .with_children(|parent| {
-            for i in 0..4 {
+            for i in 0..HOTBAR_SLOTS {
                 spawn_item_node(parent, i);
             }
         });
@@ -164,7 +270,7 @@ pub fn setup_ui(
             ExpandedInventoryIndicator,
         ))
         .with_children(|parent| {
-            for i in 4..32 {
+            for i in HOTBAR_SLOTS..32 {
                 spawn_item_node(parent, i);
             }
         });
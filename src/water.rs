@@ -0,0 +1,176 @@
+use bevy::{prelude::*, utils::HashSet};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    collisions::GameCollisions,
+    creature::{DamageEvent, DamageKind, Health},
+    map::Water,
+};
+
+/// Gravity scale of dynamic bodies in water, negative values make them float up
+const WATER_GRAVITY_SCALE: f32 = -0.4;
+const WATER_DRAG: f32 = 3.;
+
+/// Present while the entity overlaps a water volume
+#[derive(Component, Debug)]
+pub struct Submerged {
+    /// Whether the top of the collider is under the surface as well
+    pub head_under: bool,
+    /// Gravity scale to restore once the body leaves the water
+    dry_gravity_scale: f32,
+    /// Damping the body had before the water drag replaced it
+    dry_damping: Option<Damping>,
+}
+
+/// Air supply, creatures without it never drown
+#[derive(Component, Debug)]
+pub struct Breath {
+    /// Time that can be spent with the head under water
+    pub air: Timer,
+    pub drown_timer: Timer,
+    /// Health lost every time the drown timer finishes
+    pub drown_damage: isize,
+}
+
+impl Default for Breath {
+    fn default() -> Self {
+        Self {
+            air: Timer::from_seconds(10., TimerMode::Once),
+            drown_timer: Timer::from_seconds(1., TimerMode::Repeating),
+            drown_damage: 10,
+        }
+    }
+}
+
+impl Breath {
+    /// Air left as a fraction, for the breath meter
+    pub fn fraction_left(&self) -> f32 {
+        self.air.percent_left()
+    }
+}
+
+fn head_under_water(
+    rapier_context: &RapierContext,
+    transform: &GlobalTransform,
+    collider: &Collider,
+) -> bool {
+    let (scale, _, position) = transform.to_scale_rotation_translation();
+    let top = collider.raw.compute_local_aabb().maxs.y * scale.y;
+    let mut under = false;
+    rapier_context.intersections_with_point(
+        position.truncate() + Vec2::new(0., top),
+        QueryFilter::new().groups(CollisionGroups::new(
+            Group::ALL,
+            GameCollisions::Water.into(),
+        )),
+        |_| {
+            under = true;
+            false
+        },
+    );
+    under
+}
+
+/// Tracks which bodies are in water and makes dynamic ones buoyant
+pub fn detect_submersion(
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+    water: Query<(&GlobalTransform, &Collider), With<Water>>,
+    mut bodies: Query<(
+        Entity,
+        &RigidBody,
+        &GlobalTransform,
+        &Collider,
+        Option<&mut GravityScale>,
+        Option<&mut Submerged>,
+        Option<&Damping>,
+    )>,
+) {
+    let mut in_water = HashSet::new();
+    for (transform, collider) in &water {
+        rapier_context.intersections_with_shape(
+            transform.translation().truncate(),
+            0.,
+            collider,
+            QueryFilter::new().exclude_sensors(),
+            |entity| {
+                in_water.insert(entity);
+                true
+            },
+        );
+    }
+
+    for (entity, body, transform, collider, gravity_scale, submerged, damping) in &mut bodies {
+        if *body == RigidBody::Fixed {
+            continue;
+        }
+        let dynamic = *body == RigidBody::Dynamic;
+        match (in_water.contains(&entity), submerged) {
+            (true, Some(mut submerged)) => {
+                submerged.head_under = head_under_water(&rapier_context, transform, collider);
+            }
+            (true, None) => {
+                let mut dry_gravity_scale = 1.;
+                if let Some(mut gravity_scale) = gravity_scale {
+                    dry_gravity_scale = gravity_scale.0;
+                    if dynamic {
+                        gravity_scale.0 = WATER_GRAVITY_SCALE;
+                    }
+                }
+                let mut entity_commands = commands.entity(entity);
+                entity_commands.insert(Submerged {
+                    head_under: head_under_water(&rapier_context, transform, collider),
+                    dry_gravity_scale,
+                    dry_damping: damping.copied(),
+                });
+                if dynamic {
+                    entity_commands.insert(Damping {
+                        linear_damping: WATER_DRAG,
+                        angular_damping: WATER_DRAG,
+                    });
+                }
+            }
+            (false, Some(submerged)) => {
+                let mut entity_commands = commands.entity(entity);
+                entity_commands.remove::<Submerged>();
+                if dynamic {
+                    if let Some(mut gravity_scale) = gravity_scale {
+                        gravity_scale.0 = submerged.dry_gravity_scale;
+                    }
+                    match submerged.dry_damping {
+                        Some(damping) => entity_commands.insert(damping),
+                        None => entity_commands.remove::<Damping>(),
+                    };
+                }
+            }
+            (false, None) => (),
+        }
+    }
+}
+
+pub fn update_breath(
+    mut creatures: Query<(Entity, &mut Breath, Option<&Submerged>), With<Health>>,
+    mut damage_events: EventWriter<DamageEvent>,
+    time: Res<Time>,
+) {
+    for (entity, mut breath, submerged) in &mut creatures {
+        if !submerged.is_some_and(|submerged| submerged.head_under) {
+            breath.air.reset();
+            breath.drown_timer.reset();
+            continue;
+        }
+        if !breath.air.tick(time.delta()).finished() {
+            continue;
+        }
+        if breath.drown_timer.tick(time.delta()).just_finished() {
+            damage_events.send(DamageEvent {
+                target: entity,
+                source: None,
+                amount: breath.drown_damage,
+                kind: DamageKind::Drowning,
+                knockback: None,
+                direction: None,
+            });
+        }
+    }
+}
@@ -1,2 +1,267 @@
+use bevy::{prelude::*, utils::HashSet};
+use bevy_rapier2d::prelude::*;
 
+use crate::{
+    camera::CursorWorldPosition,
+    collisions::{collider_bounds, GameCollisions},
+    controls::Action,
+    creature::{Damage, DamageEvent, DamageKind},
+    enemy::Enemy,
+    inventory::Inventory,
+    items::{EntitiesResource, WeaponType},
+    player::Player,
+    projectile::{
+        aim_direction, arrow_rotation, Arrow, ProjectileBundle, ProjectilesGlobalAmount, ARROW_ITEM,
+    },
+};
 
+/// How far the sword reaches past the edge of the player
+const SWORD_REACH: f32 = 20.;
+const SWORD_HEIGHT: f32 = 16.;
+const SWING_DURATION: f32 = 0.15;
+const SWORD_KNOCKBACK: f32 = 150.;
+/// Seconds it takes to draw a bow fully
+const BOW_FULL_CHARGE: f32 = 1.;
+const ARROW_MIN_SPEED: f32 = 250.;
+const ARROW_MAX_SPEED: f32 = 700.;
+/// Share of the bow damage dealt by an arrow loosed without drawing
+const ARROW_MIN_DAMAGE: f32 = 0.3;
+const ARROW_HALF_EXTENTS: Vec2 = Vec2::new(12., 3.);
+const ARROW_SCALE: f32 = 0.5;
+
+/// Sword swing following its owner until it ends, its hitbox is a shape query
+/// instead of a collider so it never touches the physics world
+#[derive(Component, Debug)]
+pub struct MeleeSwing {
+    pub owner: Entity,
+    pub damage: isize,
+    /// Speed hit enemies are pushed away with
+    pub knockback: f32,
+    pub timer: Timer,
+    /// Enemies this swing already damaged
+    pub hit: HashSet<Entity>,
+}
+
+/// Present on the player while the attack button is held with a bow selected
+#[derive(Component, Default, Debug)]
+pub struct BowCharge {
+    pub seconds: f32,
+}
+
+impl BowCharge {
+    /// How far the bow is drawn, from 0 to 1
+    pub fn fraction(&self) -> f32 {
+        (self.seconds / BOW_FULL_CHARGE).min(1.)
+    }
+}
+
+/// Center of the hitbox, just past the edge of the owner's collider
+fn hitbox_position(owner: &Transform, collider: &Collider, facing_left: bool) -> Vec2 {
+    let (center, half_extents) = collider_bounds(collider);
+    let center = owner.translation.truncate() + center * owner.scale.truncate();
+    let x = half_extents.x * owner.scale.x + SWORD_REACH / 2.;
+    center + Vec2::new(if facing_left { -x } else { x }, 0.)
+}
+
+/// Distance from the center of a box to its edge along a normalized direction
+fn distance_to_edge(half_extents: Vec2, direction: Vec2) -> f32 {
+    let x = half_extents.x / direction.x.abs();
+    let y = half_extents.y / direction.y.abs();
+    // dividing by zero gives infinity, the other axis is the one crossed
+    x.min(y)
+}
+
+pub fn swing_sword(
+    actions: Res<Input<Action>>,
+    inventory: Res<Inventory>,
+    mut commands: Commands,
+    players: Query<(Entity, &Transform, &Collider, &TextureAtlasSprite), With<Player>>,
+    swings: Query<&MeleeSwing>,
+) {
+    if !actions.just_pressed(Action::Attack) {
+        return;
+    }
+    let Some(sword) = inventory
+        .selected_weapon()
+        .filter(|weapon| weapon.weapon_type == WeaponType::Sword)
+    else {
+        return;
+    };
+
+    for (player, transform, collider, sprite) in &players {
+        if swings.iter().any(|swing| swing.owner == player) {
+            continue;
+        }
+        let position = hitbox_position(transform, collider, sprite.flip_x);
+        commands.spawn((
+            MeleeSwing {
+                owner: player,
+                damage: sword.damage as isize,
+                knockback: SWORD_KNOCKBACK,
+                timer: Timer::from_seconds(SWING_DURATION, TimerMode::Once),
+                hit: HashSet::new(),
+            },
+            TransformBundle::from_transform(Transform::from_translation(
+                position.extend(transform.translation.z),
+            )),
+        ));
+    }
+}
+
+/// Damages and knocks back every enemy overlapping a swing, once per swing
+pub fn melee_hits(
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+    time: Res<Time>,
+    mut swings: Query<(Entity, &mut MeleeSwing, &mut Transform)>,
+    players: Query<
+        (&Transform, &Collider, &TextureAtlasSprite),
+        (With<Player>, Without<MeleeSwing>),
+    >,
+    enemies: Query<&Transform, (With<Enemy>, Without<MeleeSwing>)>,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    let hitbox = Collider::cuboid(SWORD_REACH / 2., SWORD_HEIGHT / 2.);
+    let groups = CollisionGroups::new(GameCollisions::Player.into(), GameCollisions::Mob.into());
+    for (entity, mut swing, mut transform) in &mut swings {
+        let Ok((owner_transform, owner_collider, owner_sprite)) = players.get(swing.owner) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        if swing.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let position = hitbox_position(owner_transform, owner_collider, owner_sprite.flip_x);
+        transform.translation = position.extend(transform.translation.z);
+
+        let mut overlapping = Vec::new();
+        rapier_context.intersections_with_shape(
+            position,
+            0.,
+            &hitbox,
+            QueryFilter::new().groups(groups),
+            |other| {
+                overlapping.push(other);
+                true
+            },
+        );
+
+        for enemy in overlapping {
+            if swing.hit.contains(&enemy) {
+                continue;
+            }
+            let Ok(enemy_transform) = enemies.get(enemy) else {
+                continue;
+            };
+            swing.hit.insert(enemy);
+
+            let direction = if enemy_transform.translation.x < owner_transform.translation.x {
+                -1.
+            } else {
+                1.
+            };
+            damage_events.send(DamageEvent {
+                target: enemy,
+                source: Some(swing.owner),
+                amount: swing.damage,
+                kind: DamageKind::Physical,
+                knockback: Some(Vec2::new(direction * swing.knockback, 0.)),
+                direction: Some(Vec2::new(direction, 0.)),
+            });
+        }
+    }
+}
+
+/// Draws the selected bow while attack is held and looses an arrow on release,
+/// a longer draw shoots faster and harder
+#[allow(clippy::too_many_arguments)]
+pub fn draw_bow(
+    actions: Res<Input<Action>>,
+    mut inventory: ResMut<Inventory>,
+    time: Res<Time>,
+    assets: Res<EntitiesResource>,
+    cursor_position: Res<CursorWorldPosition>,
+    mut projectiles_amount: ResMut<ProjectilesGlobalAmount>,
+    mut commands: Commands,
+    mut players: Query<
+        (
+            Entity,
+            &Transform,
+            &Collider,
+            &TextureAtlasSprite,
+            Option<&mut BowCharge>,
+        ),
+        With<Player>,
+    >,
+) {
+    let bow = inventory
+        .selected_weapon()
+        .filter(|weapon| weapon.weapon_type == WeaponType::Bow);
+
+    for (player, transform, collider, sprite, charge) in &mut players {
+        let Some(bow) = bow else {
+            if charge.is_some() {
+                commands.entity(player).remove::<BowCharge>();
+            }
+            continue;
+        };
+        let Some(mut charge) = charge else {
+            if actions.just_pressed(Action::Attack) && inventory.count(ARROW_ITEM) > 0 {
+                commands.entity(player).insert(BowCharge::default());
+            }
+            continue;
+        };
+        if actions.pressed(Action::Attack) {
+            charge.seconds += time.delta_seconds();
+            continue;
+        }
+        commands.entity(player).remove::<BowCharge>();
+        if !inventory.take(ARROW_ITEM) {
+            continue;
+        }
+
+        let fraction = charge.fraction();
+        let player_position = transform.translation.truncate();
+        let direction = aim_direction(cursor_position.0, player_position, sprite.flip_x);
+        let speed = ARROW_MIN_SPEED + (ARROW_MAX_SPEED - ARROW_MIN_SPEED) * fraction;
+        let damage = bow.damage as f32 * (ARROW_MIN_DAMAGE + (1. - ARROW_MIN_DAMAGE) * fraction);
+        // loosed clear of the player whatever way the arrow is rotated
+        let (center, half_extents) = collider_bounds(collider);
+        let center = player_position + center * transform.scale.truncate();
+        let clearance = distance_to_edge(half_extents * transform.scale.truncate(), direction)
+            + ARROW_HALF_EXTENTS.length() * ARROW_SCALE;
+        let origin = (center + direction * clearance).extend(transform.translation.z);
+
+        let arrow = assets.entities.get(ARROW_ITEM).expect("arrow to exist");
+        projectiles_amount.queue.push_front(
+            commands
+                .spawn((
+                    ProjectileBundle {
+                        rigid_body: RigidBody::Dynamic,
+                        collider: Collider::cuboid(ARROW_HALF_EXTENTS.x, ARROW_HALF_EXTENTS.y),
+                        density: ColliderMassProperties::Mass(0.5),
+                        gravity_scale: GravityScale(1.),
+                        rotation_constraints: LockedAxes::ROTATION_LOCKED,
+                        velocity: Velocity::linear(direction * speed),
+                        ccd: Ccd::enabled(),
+                        collision_groups: GameCollisions::Projectile.into(),
+                        damage: Damage::new(damage.round().max(1.) as isize, DamageKind::Physical),
+                        ..Default::default()
+                    },
+                    Arrow,
+                    SpriteSheetBundle {
+                        texture_atlas: arrow.texture_atlas.clone(),
+                        sprite: TextureAtlasSprite::new(arrow.index),
+                        transform: Transform {
+                            translation: origin,
+                            scale: Vec3::splat(ARROW_SCALE),
+                            rotation: arrow_rotation(direction),
+                        },
+                        ..Default::default()
+                    },
+                ))
+                .id(),
+        );
+    }
+}